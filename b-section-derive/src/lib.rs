@@ -0,0 +1,162 @@
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Derives targets for searching records by a single field.
+///
+/// For a struct `Record`, this generates:
+/// - `Record::target()`, which returns a builder with a method per field, such as
+///   `Record::target().timestamp(Op::Ge, x)`, that creates a target comparing that field against `x` using the operator;
+/// - `RecordTarget`, the type of these targets, which implements `FindOrd<Record, E>` for any error type `E`;
+/// - `RecordTargetBuilder` and `RecordTargetField` used by the above.
+///
+/// The fields must implement `PartialOrd`. Fields are configured using the `find_target` attribute:
+/// - `#[find_target(snap = "...")]` sets the snapping of `Op::Eq` targets for the field
+///   to one of "none" (the default), "down", "up", or "both";
+/// - `#[find_target(skip)]` omits the builder method for the field.
+#[proc_macro_derive(FindTarget, attributes(find_target))]
+pub fn derive_find_target(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+struct FieldOpts {
+    skip: bool,
+    snap: Ident,
+}
+
+fn parse_field_opts(attrs: &[Attribute]) -> Result<FieldOpts> {
+    let mut opts = FieldOpts { skip: false, snap: format_ident!("None") };
+    for attr in attrs.iter().filter(|a| a.path().is_ident("find_target")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                opts.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("snap") {
+                let s: LitStr = meta.value()?.parse()?;
+                let snap = match s.value().as_str() {
+                    "none" => "None",
+                    "down" => "Down",
+                    "up" => "Up",
+                    "both" => "Both",
+                    _ => return Err(Error::new(s.span(), "snap must be one of \"none\", \"down\", \"up\", or \"both\"")),
+                };
+                opts.snap = format_ident!("{}", snap);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported find_target attribute"))
+            }
+        })?;
+    }
+    Ok(opts)
+}
+
+/// Converts a field name like `created_at` into a variant name like `CreatedAt`.
+fn to_camel_case(ident: &Ident) -> Ident {
+    let name = ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .map(|w| {
+            let mut cs = w.chars();
+            cs.next().map_or(String::new(), |c| c.to_uppercase().chain(cs).collect())
+        })
+        .collect::<String>();
+    format_ident!("{}", name, span = ident.span())
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "FindTarget cannot be derived for generic types"));
+    }
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(fs) => &fs.named,
+            _ => return Err(Error::new_spanned(&input.ident, "FindTarget can only be derived for structs with named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "FindTarget can only be derived for structs")),
+    };
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let target = format_ident!("{}Target", name);
+    let builder = format_ident!("{}TargetBuilder", name);
+    let field_enum = format_ident!("{}TargetField", name);
+
+    let mut idents = Vec::new();
+    let mut variants = Vec::new();
+    let mut types = Vec::new();
+    let mut snaps = Vec::new();
+    for f in fields {
+        let opts = parse_field_opts(&f.attrs)?;
+        if opts.skip {
+            continue;
+        }
+        let ident = f.ident.as_ref().expect("named field");
+        variants.push(to_camel_case(ident));
+        idents.push(ident);
+        types.push(&f.ty);
+        snaps.push(opts.snap);
+    }
+
+    let target_doc = format!("Target comparing a single field of [`{}`] using an operator.", name);
+    let builder_doc = format!("Builder of [`{}`] with a method per field.", target);
+    let field_doc = format!("Field of [`{}`] along with the value that [`{}`] compares it against.", name, target);
+    let method_docs = idents.iter().map(|i| format!("Creates a target comparing the field `{}` against `value` using `op`.", i.unraw()));
+
+    Ok(quote! {
+        impl #name {
+            /// Returns a builder of targets for searching by a single field.
+            #vis fn target() -> #builder {
+                #builder { _private: () }
+            }
+        }
+
+        #[doc = #builder_doc]
+        #vis struct #builder {
+            _private: (),
+        }
+
+        impl #builder {
+            #(
+                #[doc = #method_docs]
+                #vis fn #idents(self, op: ::b_section::op::Op, value: #types) -> #target {
+                    #target {
+                        op,
+                        snap: ::b_section::snap::SnapPolicy::#snaps,
+                        field: #field_enum::#variants(value),
+                    }
+                }
+            )*
+        }
+
+        #[doc = #field_doc]
+        #vis enum #field_enum {
+            #( #variants(#types), )*
+        }
+
+        #[doc = #target_doc]
+        #vis struct #target {
+            pub op: ::b_section::op::Op,
+            /// Snapping of the target if `op` is `Op::Eq`.
+            pub snap: ::b_section::snap::SnapPolicy,
+            pub field: #field_enum,
+        }
+
+        impl<E> ::b_section::find::FindOrd<#name, E> for #target {
+            fn cmp(&self, t: &#name) -> ::std::result::Result<::b_section::find::FindOrdering, E> {
+                Ok(
+                    match self.field {
+                        #(
+                            #field_enum::#variants(ref v) => {
+                                self.op.find_ordering(::std::cmp::PartialOrd::partial_cmp(v, &t.#idents), self.snap)
+                            }
+                        )*
+                    }
+                )
+            }
+        }
+    })
+}
@@ -0,0 +1,56 @@
+use b_section::find::{find, FindOrd};
+use b_section::find_range::find_range;
+use b_section::op::Op;
+use b_section_derive::FindTarget;
+
+#[derive(Clone, Debug, PartialEq, FindTarget)]
+struct Record {
+    timestamp: u64,
+    #[find_target(snap = "down")]
+    seq: u32,
+    #[find_target(skip)]
+    #[allow(dead_code)]
+    payload: Vec<u8>,
+}
+
+fn records() -> Vec<Record> {
+    [(10, 1), (20, 2), (20, 3), (30, 4)]
+        .into_iter()
+        .map(|(timestamp, seq)| Record { timestamp, seq, payload: vec![] })
+        .collect()
+}
+
+// Counterpart of `find_idx` in the library's test helpers, which aren't available to integration tests.
+fn find_idx(arr: &[Record], target: &impl FindOrd<Record, String>) -> Option<i64> {
+    let lookup = |idx| Ok(arr[idx as usize].clone());
+    find(&lookup, target, 0, arr.len() as i64 - 1).unwrap().element.map(|e| e.idx)
+}
+
+#[test]
+fn can_find_record_by_field() {
+    let arr = records();
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Eq, 30)), Some(3));
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Eq, 25)), None);
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Ge, 20)), Some(1));
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Gt, 20)), Some(3));
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Le, 20)), Some(2));
+    assert_eq!(find_idx(&arr, &Record::target().timestamp(Op::Lt, 20)), Some(0));
+}
+
+#[test]
+fn equality_target_uses_snapping_of_field() {
+    let arr = records();
+    assert_eq!(find_idx(&arr, &Record::target().seq(Op::Eq, 5)), Some(3));
+    let mut target = Record::target().timestamp(Op::Eq, 25);
+    assert_eq!(find_idx(&arr, &target), None);
+    target.snap = b_section::snap::SnapPolicy::Up;
+    assert_eq!(find_idx(&arr, &target), Some(3));
+}
+
+#[test]
+fn can_find_range_of_records_by_field() {
+    let arr = records();
+    let lookup = |idx| Ok::<_, String>(arr[idx as usize].clone());
+    let (lower, upper) = find_range(&lookup, &Record::target().timestamp(Op::Ge, 15), &Record::target().timestamp(Op::Le, 20), 0, 3).unwrap();
+    assert_eq!((lower.map(|e| e.idx), upper.map(|e| e.idx)), (Some(1), Some(2)));
+}
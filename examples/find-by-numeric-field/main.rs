@@ -1,37 +1,130 @@
+mod output;
 mod pair;
+mod query;
 mod target;
+mod value;
 
+use crate::output::{print_count, print_result, Format, Match, Side};
 use crate::pair::{Op, Pair};
-use crate::target::{data_to_string, Data, DataTarget, Target};
+use crate::query::Expr;
+use crate::target::{BoxedTarget, Data, DataTarget, Equality, Field, Target};
+use crate::value::{infer_types, Type, Types, Value};
 use anyhow::{anyhow, Context, Error, Result};
 use b_section::combine::{FindOrdCombineLower, FindOrdCombineUpper};
-use b_section::find::{find, Element, FindOrd};
-use b_section::find_range::find_range;
+use b_section::ext::FindOrdExt;
+use b_section::find::{find, Element, FindOrd, FindOrdering, IncomparablePolicy};
+use b_section::find_range::{find_range, FindRangeResult};
+use b_section::float::Tolerance;
+use b_section::snap::SnapPolicy;
 use clap::Parser;
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::cmp::min;
 use std::io::stdin;
+use std::ops::Range;
 
 #[derive(Parser, Debug)]
 struct Args {
-    #[clap(long = "from", help = "Lower constraint.")]
+    #[clap(long = "from", help = "Lower constraint. A tolerance given as 'value±tolerance' limits how far the constraint snaps.")]
     from: Vec<String>,
-    #[clap(long = "to", help = "Upper constraint.")]
+    #[clap(long = "to", help = "Upper constraint. A tolerance given as 'value±tolerance' limits how far the constraint snaps.")]
     to: Vec<String>,
+    #[clap(long = "where", help = "Constraint using one of the operators '<', '<=', '>', '>=', or '==', which determines whether it's a lower or upper constraint (or both).")]
+    where_: Vec<String>,
+    #[clap(long = "query", help = "Constraints as a boolean expression like '(a>=1 and b>2) or a>=3' using the operators of '--where'. Every top-level 'and' term must be monotone, i.e. only constrain one side.")]
+    query: Option<String>,
+    #[clap(long = "type", help = "Type of a field as 'name=type' with type being one of 'int', 'float', 'string', 'timestamp', 'duration', 'natural' (strings with numbers compared numerically), or 'semver'. The types of fields not specified are inferred from all of their values.")]
+    types: Vec<String>,
+    #[clap(long = "range", help = "Print all records from the lower to the upper match (inclusive).")]
+    range: bool,
+    #[clap(long = "limit", requires = "range", help = "Maximum number of records of the range to print.")]
+    limit: Option<usize>,
+    #[clap(long = "count-only", requires = "range", help = "Print only the number of records in the range.")]
+    count_only: bool,
+    #[clap(short = 'C', long = "context", requires = "range", default_value_t = 0, help = "Number of records to print before and after the range.")]
+    context: usize,
+    #[clap(long = "epsilon", value_parser = parse_epsilon, help = "Tolerance within which floats match a constraint's value: 'abs:x', 'rel:x', or 'ulps:n' for an absolute, relative, or ULP-based tolerance. A plain number is an absolute tolerance.")]
+    epsilon: Option<Tolerance>,
+    #[clap(long = "incomparable", value_parser = parse_incomparable_policy, default_value = "skip", help = "Handling of records with values that are incomparable with a constraint (i.e. NaN): 'error', 'below', 'above', or 'skip'.")]
+    incomparable: IncomparablePolicy,
+    #[clap(long = "output", value_enum, default_value_t = Format::Text, help = "Output format of the matched records (or of the range with '--range').")]
+    output: Format,
 }
 
-fn parse_stdin_records(s: String) -> Result<HashMap<String, f64>> {
+fn parse_types(ss: Vec<String>) -> Result<Types> {
+    ss.iter()
+        .map(|s| {
+            let Pair { name, op, value } = Pair::parse(s)?;
+            if op != Op::Equals {
+                return Err(anyhow!("invalid op '{:?}'", op));
+            }
+            Ok((name, value.parse::<Type>()?))
+        })
+        .collect()
+}
+
+fn parse_incomparable_policy(s: &str) -> Result<IncomparablePolicy> {
+    match s {
+        "error" => Ok(IncomparablePolicy::Error),
+        "below" => Ok(IncomparablePolicy::Below),
+        "above" => Ok(IncomparablePolicy::Above),
+        "skip" => Ok(IncomparablePolicy::Skip),
+        x => Err(anyhow!("invalid policy '{}'", x)),
+    }
+}
+
+fn parse_epsilon(s: &str) -> Result<Tolerance> {
+    let (kind, value) = s.split_once(':').unwrap_or(("abs", s));
+    match kind {
+        "abs" => Ok(Tolerance::Absolute(value.parse()?)),
+        "rel" => Ok(Tolerance::Relative(value.parse()?)),
+        "ulps" => Ok(Tolerance::Ulps(value.parse()?)),
+        x => Err(anyhow!("invalid tolerance kind '{}'", x)),
+    }
+}
+
+/// Splits a record into its fields as `(name, value)`.
+fn split_stdin_record(s: &str) -> Result<Vec<(String, String)>> {
     s.split(" ")
         .map(|p| {
             let Pair { name, op, value } = Pair::parse(p)?;
             if op != Op::Equals {
                 return Err(anyhow!("invalid op '{:?}'", op));
             }
-            Ok((name, value.parse()?))
+            Ok((name, value))
         })
         .collect()
 }
 
-pub fn new_lookup(datas: &Vec<Data>) -> impl Fn(i64) -> Result<Data> + '_ {
+fn parse_fields(fields: Vec<(String, String)>, types: &Types) -> Result<Data> {
+    let fields =
+        fields
+            .into_iter()
+            .map(|(name, value)| {
+                let parsed = Value::parse(&value, types.get(&name).copied())?;
+                Ok(Field { name, value: parsed, text: value })
+            })
+            .collect::<Result<_>>()?;
+    Data::new(fields)
+}
+
+/// Parses the records, inferring the types of the fields that aren't typed explicitly from all of their values.
+/// Returns the records along with the types of all fields.
+fn parse_all_records(lines: &[String], types: &Types) -> Result<(Vec<Data>, Types)> {
+    let records =
+        lines
+            .iter()
+            .map(|l| split_stdin_record(l))
+            .collect::<Result<Vec<_>>>()?;
+    let types = infer_types(records.iter().flatten().map(|(n, v)| (n.as_str(), v.as_str())), types);
+    let datas =
+        records
+            .into_iter()
+            .map(|r| parse_fields(r, &types))
+            .collect::<Result<_>>()?;
+    Ok((datas, types))
+}
+
+pub fn new_lookup(datas: &[Data]) -> impl Fn(i64) -> Result<Data> + '_ {
     |idx| {
         if idx < 0 {
             return Err(anyhow!("negative index {}", idx));
@@ -48,40 +141,154 @@ fn map_to_pairs(ss: Vec<String>) -> Result<Vec<Pair>> {
     ss.iter().map(|s| s.as_str()).map(Pair::parse).collect()
 }
 
-fn map_to_targets(ps: Vec<Pair>, t: Target) -> Result<Vec<DataTarget>> {
-    ps.into_iter().map(|p| DataTarget::from_pair(p, t.clone())).collect()
-}
-
-fn resolve_snap(ds: Vec<DataTarget>) -> Option<(Vec<Box<dyn FindOrd<Data, Error>>>, bool, bool)> {
-    let mut snap_downwards = None; // will be 'Some' iff 'snap_downwards' of all targets are the same
-    let mut snap_upwards = None; // will be 'Some' iff 'snap_upwards' of all targets are the same
-    let mut fs: Vec<Box<dyn FindOrd<Data, Error>>> = Vec::with_capacity(ds.len()); // converting element type
-    for f in ds.into_iter() {
-        let d = f.snap_downwards;
-        if let Some(s) = snap_downwards {
-            if s != d {
-                // Value of 'snap_downwards' differs from that of previous targets.
-                return None;
-            }
-        } else {
-            // First target: Just capture value.
-            snap_downwards = Some(d);
+/// Splits constraints into lower and upper ones based on their operators.
+fn split_by_target(ps: Vec<Pair>) -> Result<(Vec<Pair>, Vec<Pair>)> {
+    let mut lower = Vec::new();
+    let mut upper = Vec::new();
+    for p in ps {
+        let ts = Target::from_op(p.op);
+        if ts.is_empty() {
+            return Err(anyhow!("operator '{}' of constraint '{}' doesn't determine a bound: use '--from' or '--to'", p.op.as_str(), p.name));
         }
-        let u = f.snap_upwards;
-        if let Some(s) = snap_upwards {
-            if s != u {
-                // Value of 'snap_upwards' differs from that of previous targets.
-                return None;
+        for t in ts {
+            match t {
+                Target::Lower => lower.push(p.clone()),
+                Target::Upper => upper.push(p.clone()),
             }
-        } else {
-            // First target: Just capture value.
-            snap_upwards = Some(u);
         }
-        fs.push(Box::new(f));
     }
-    match (snap_downwards, snap_upwards) {
-        (Some(d), Some(u)) => Some((fs, d, u)),
-        _ => None,
+    Ok((lower, upper))
+}
+
+fn map_to_targets(ps: &[Pair], t: Target, types: &Types, epsilon: Option<Tolerance>, equality: Equality) -> Result<Vec<BoxedTarget>> {
+    ps.iter().map(|p| Ok(Box::new(DataTarget::from_pair(p.clone(), t.clone(), types, epsilon, equality)?) as BoxedTarget)).collect()
+}
+
+/// Constraints of the lower and upper bound.
+struct Constraints {
+    from: Vec<Pair>,
+    to: Vec<Pair>,
+    query: Option<Expr>,
+}
+
+impl Constraints {
+    /// Combines the constraints into a target for each bound.
+    /// A bound without any constraints has no target (i.e. the range is open in that direction).
+    fn targets(
+        &self,
+        types: &Types,
+        epsilon: Option<Tolerance>,
+        incomparable: IncomparablePolicy,
+        equality: Equality,
+    ) -> Result<(Option<BoxedTarget>, Option<BoxedTarget>)> {
+        let (query_from, query_to) = match &self.query {
+            None => (vec![], vec![]),
+            Some(q) => q.clone().compile(types, epsilon, equality)?,
+        };
+        let mut lower_target_combined = map_to_targets(&self.from, Target::Lower, types, epsilon, equality)?;
+        let mut upper_target_combined = map_to_targets(&self.to, Target::Upper, types, epsilon, equality)?;
+        lower_target_combined.extend(query_from);
+        upper_target_combined.extend(query_to);
+        // The snapping of each constraint is kept within the combinations.
+        let lower_target =
+            if lower_target_combined.is_empty() {
+                None
+            } else {
+                Some(Box::new(FindOrdCombineUpper { combined: lower_target_combined, snap: SnapPolicy::None }.on_incomparable(incomparable)) as BoxedTarget)
+            };
+        let upper_target =
+            if upper_target_combined.is_empty() {
+                None
+            } else {
+                Some(Box::new(FindOrdCombineLower { combined: upper_target_combined, snap: SnapPolicy::None }.on_incomparable(incomparable)) as BoxedTarget)
+            };
+        Ok((lower_target, upper_target))
+    }
+}
+
+/// Determines the (half-open) line index range to print for the (inclusive) index range `[start; end]`:
+/// The lines of the range itself capped by `limit`, along with `context` lines on each side of the printed ones.
+/// Like for `grep -m`, the context after a truncated range follows the last printed line.
+fn range_line_idxs(
+    len: usize,
+    (start, end): (i64, i64),
+    limit: Option<usize>,
+    context: usize,
+) -> Range<usize> {
+    let count = if start <= end { (end - start + 1) as usize } else { 0 };
+    let count = limit.map_or(count, |l| min(l, count));
+    if count == 0 {
+        return 0..0;
+    }
+    let start = start as usize;
+    start.saturating_sub(context)..min(start + count + context, len)
+}
+
+/// Prints the records in the (inclusive) index range `[start; end]` along with `context` records on each side.
+/// The number of printed records of the range itself is capped by `limit`.
+/// If `count_only` is set, only the number of records that would have been printed from the range is printed.
+fn print_range(
+    format: Format,
+    lines: &[String],
+    datas: &[Data],
+    (start, end): (i64, i64),
+    limit: Option<usize>,
+    count_only: bool,
+    context: usize,
+) -> Result<()> {
+    let count = if start <= end { (end - start + 1) as usize } else { 0 };
+    if count_only {
+        return print_count(format, limit.map_or(count, |l| min(l, count)));
+    }
+    let idxs = range_line_idxs(lines.len(), (start, end), limit, context);
+    let range = if count == 0 { 0..0 } else { start as usize..end as usize + 1 };
+    output::print_range(format, lines, datas, idxs, range)
+}
+
+/// Searches the `len` records for the lower and upper bound.
+/// The bounds are the first and last of the records that satisfy the constraints (or the ones found by snapping).
+fn find_bounds(
+    lookup: &impl Fn(i64) -> Result<Data>,
+    len: usize,
+    lower_target: &Option<BoxedTarget>,
+    upper_target: &Option<BoxedTarget>,
+) -> Result<FindRangeResult<Data>> {
+    let upper_idx = len as i64 - 1;
+    Ok(
+        match (lower_target, upper_target) {
+            (Some(lt), Some(ut)) => find_range(lookup, lt, ut, 0, upper_idx)?,
+            (Some(t), None) => (find(lookup, t, 0, upper_idx)?.element, None),
+            (None, Some(t)) => (None, find(lookup, t, 0, upper_idx)?.element),
+            (None, None) => (None, None),
+        }
+    )
+}
+
+/// Determines the (inclusive) index range of the records between the found bounds.
+/// A missing target leaves the range open in that direction,
+/// but a target without a result means that the range is empty.
+fn matched_range(len: usize, has_lower_target: bool, has_upper_target: bool, bounds: &FindRangeResult<Data>) -> (i64, i64) {
+    match (has_lower_target, bounds, has_upper_target) {
+        (true, (None, _), _) | (_, (_, None), true) => (0, -1),
+        (_, (l, u), _) => (
+            l.as_ref().map_or(0, |e| e.idx),
+            u.as_ref().map_or(len as i64 - 1, |e| e.idx),
+        ),
+    }
+}
+
+/// Determines whether the element was an exact match for the target or the result of snapping.
+/// The target must be built using [`Equality::Match`] (as the targets of the search never report matches).
+fn classify(target: &Option<impl FindOrd<Data, Error>>, e: Option<Element<Data>>) -> Result<Side> {
+    match (target, e) {
+        (Some(t), Some(e)) => {
+            let m = match t.cmp(&e.val)? {
+                FindOrdering::ValMatchesTarget => Match::Exact,
+                _ => Match::Snap,
+            };
+            Ok(Some((e, m)))
+        }
+        _ => Ok(None),
     }
 }
 
@@ -90,61 +297,118 @@ fn main() -> Result<()> {
     let args = Args::parse();
     let from = args.from;
     let to = args.to;
+    let types = parse_types(args.types)?;
 
-    let lower_target_combined = map_to_targets(map_to_pairs(from)?, Target::Lower)?;
-    let upper_target_combined = map_to_targets(map_to_pairs(to)?, Target::Upper)?;
-    let lower_target =
-        if lower_target_combined.is_empty() {
-            None
-        } else {
-            let (combined, snap_downwards, snap_upwards) =
-                resolve_snap(lower_target_combined)
-                    .ok_or(anyhow!("invalid combination of '--from' constraints: mixed usage of '=' and '~'"))?;
-            Some(FindOrdCombineUpper { combined, snap_downwards, snap_upwards })
-        };
-    let upper_target =
-        if upper_target_combined.is_empty() {
-            None
-        } else {
-            let (combined, snap_downwards, snap_upwards) =
-                resolve_snap(upper_target_combined)
-                    .ok_or(anyhow!("invalid combination of '--to' constraints: mixed usage of '=' and '~'"))?;
-            Some(FindOrdCombineLower { combined, snap_downwards, snap_upwards })
-        };
+    let (where_from, where_to) = split_by_target(map_to_pairs(args.where_)?)?;
+    let constraints = Constraints {
+        from: map_to_pairs(from)?.into_iter().chain(where_from).collect(),
+        to: map_to_pairs(to)?.into_iter().chain(where_to).collect(),
+        query: args.query.map(|q| Expr::parse(&q)).transpose()?,
+    };
 
-    // Parse records from stdin.
-    let datas =
-        stdin()
-            .lines()
-            .map(|l| parse_stdin_records(l?).context("cannot parse records on stdin"))
-            .collect::<Result<Vec<Data>>>()?;
-
-    // Run bisection.
-    let (lower, upper) = match (lower_target, upper_target) {
-        (Some(lt), Some(ut)) => {
-            find_range(&new_lookup(&datas), &lt, &ut, 0, datas.len() as i64 - 1)?
-        }
-        (Some(t), None) => (
-            find(&new_lookup(&datas), &t, 0, datas.len() as i64 - 1)?.element,
-            None,
-        ),
-        (None, Some(t)) => (
-            None,
-            find(&new_lookup(&datas), &t, 0, datas.len() as i64 - 1)?.element,
-        ),
-        (None, None) => (None, None),
+    // Parse records from stdin (keeping the original lines for printing ranges).
+    // The types of the values of the constraints are those of the fields that they constrain.
+    let lines = stdin().lines().collect::<Result<Vec<String>, _>>()?;
+    let (datas, types) = parse_all_records(&lines, &types).context("cannot parse records on stdin")?;
+    let (lower_target, upper_target) = constraints.targets(&types, args.epsilon, args.incomparable, Equality::Bound)?;
+
+    // Run bisection (counting the number of records that are looked up).
+    let probes = Cell::new(0);
+    let datas_lookup = new_lookup(&datas);
+    let lookup = |idx| {
+        probes.set(probes.get() + 1);
+        datas_lookup(idx)
     };
+    let bounds = find_bounds(&lookup, datas.len(), &lower_target, &upper_target)?;
 
     // Print results.
-    if let Some(Element { val, idx }) = lower {
-        println!("LOWER: index {}: {:?}", idx, data_to_string(val));
-    } else {
-        println!("LOWER: none!");
+    if args.range {
+        let range = matched_range(datas.len(), lower_target.is_some(), upper_target.is_some(), &bounds);
+        return print_range(args.output, &lines, &datas, range, args.limit, args.count_only, args.context);
     }
-    if let Some(Element { val, idx }) = upper {
-        println!("UPPER: index {}: {:?}", idx, data_to_string(val))
-    } else {
-        println!("UPPER: none!");
+    let (lower, upper) = bounds;
+    let (lower_exact, upper_exact) = constraints.targets(&types, args.epsilon, args.incomparable, Equality::Match)?;
+    print_result(
+        args.output,
+        classify(&lower_exact, lower)?,
+        classify(&upper_exact, upper)?,
+        probes.get(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(datas: &[&str], from: &[&str], to: &[&str]) -> (i64, i64) {
+        let lines: Vec<String> = datas.iter().map(|s| s.to_string()).collect();
+        let (datas, types) = parse_all_records(&lines, &Types::new()).unwrap();
+        let pairs = |ss: &[&str]| ss.iter().map(|s| Pair::parse(s).unwrap()).collect();
+        let constraints = Constraints { from: pairs(from), to: pairs(to), query: None };
+        let (lt, ut) = constraints.targets(&types, None, IncomparablePolicy::Skip, Equality::Bound).unwrap();
+        let bounds = find_bounds(&new_lookup(&datas), datas.len(), &lt, &ut).unwrap();
+        matched_range(datas.len(), lt.is_some(), ut.is_some(), &bounds)
+    }
+
+    #[test]
+    fn range_includes_duplicates_at_both_ends() {
+        let datas = ["a=1", "a=2", "a=2", "a=2", "a=3", "a=3", "a=3", "a=4", "a=5", "a=5", "a=5", "a=6"];
+        assert_eq!(range(&datas, &["a=2"], &["a=5"]), (1, 10));
+        assert_eq!(range(&datas, &["a>=2"], &["a<=5"]), (1, 10));
+        assert_eq!(range(&datas, &["a=3"], &["a=3"]), (4, 6));
+        assert_eq!(range(&datas, &["a==3"], &["a==3"]), (4, 6));
+        assert_eq!(range(&datas, &["a>2"], &["a<5"]), (4, 7));
+        assert_eq!(range(&datas, &["a=2"], &[]), (1, 11));
+        assert_eq!(range(&datas, &[], &["a=5"]), (0, 10));
+        assert_eq!(range(&["a=2", "a=2", "a=2"], &["a=2"], &["a=2"]), (0, 2));
+    }
+
+    #[test]
+    fn field_mixing_numbers_and_strings_is_compared_as_strings() {
+        let datas = ["id=10", "id=5", "id=5", "id=a5", "id=b"];
+        assert_eq!(range(&datas, &["id>=5"], &["id<a5"]), (1, 2));
+        assert_eq!(range(&datas, &["id>=10"], &["id<=a5"]), (0, 3));
+    }
+
+    #[test]
+    fn range_is_empty_if_a_bound_is_not_found() {
+        let datas = ["a=1", "a=1", "a=3", "a=3"];
+        assert_eq!(range(&datas, &["a==2"], &["a==2"]), (0, -1));
+        assert_eq!(range(&datas, &["a=4"], &[]), (0, -1));
+        assert_eq!(range(&datas, &[], &["a=0"]), (0, -1));
+        assert_eq!(range(&datas, &[], &[]), (0, 3));
+    }
+
+    #[test]
+    fn range_line_idxs_of_empty_range() {
+        assert!(range_line_idxs(10, (0, -1), None, 0).is_empty());
+        assert!(range_line_idxs(10, (5, 4), None, 2).is_empty());
+        assert!(range_line_idxs(10, (5, 4), Some(3), 2).is_empty());
+    }
+
+    #[test]
+    fn range_line_idxs_with_limit() {
+        assert_eq!(range_line_idxs(10, (2, 6), None, 0), 2..7);
+        assert_eq!(range_line_idxs(10, (2, 6), Some(3), 0), 2..5);
+        assert_eq!(range_line_idxs(10, (2, 6), Some(5), 0), 2..7);
+        assert_eq!(range_line_idxs(10, (2, 6), Some(100), 0), 2..7);
+        assert!(range_line_idxs(10, (2, 6), Some(0), 0).is_empty());
+    }
+
+    #[test]
+    fn range_line_idxs_with_context() {
+        assert_eq!(range_line_idxs(10, (2, 6), None, 1), 1..8);
+        assert_eq!(range_line_idxs(10, (2, 6), None, 5), 0..10);
+        assert_eq!(range_line_idxs(10, (0, 9), None, 1), 0..10);
+    }
+
+    #[test]
+    fn range_line_idxs_with_limit_and_context() {
+        // The context after a truncated range follows the last printed line.
+        assert_eq!(range_line_idxs(10, (2, 6), Some(2), 1), 1..5);
+        assert_eq!(range_line_idxs(10, (2, 6), Some(4), 1), 1..7);
+        assert_eq!(range_line_idxs(10, (2, 6), Some(5), 1), 1..8);
+        assert_eq!(range_line_idxs(10, (2, 9), Some(2), 1), 1..5);
+        assert!(range_line_idxs(10, (2, 6), Some(0), 1).is_empty());
     }
-    Ok(())
 }
@@ -0,0 +1,250 @@
+use crate::target::{data_to_string, Data, Field};
+use crate::value::Value as DataValue;
+use anyhow::Result;
+use b_section::find::Element;
+use clap::ValueEnum;
+use serde_json::{json, Map, Number, Value};
+use std::io::{stdout, BufWriter, Write};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    /// Human readable output with the fields of records sorted by name
+    /// (except for ranges, which are printed exactly like the input lines).
+    Text,
+    /// JSON with the fields of records in input order.
+    Json,
+    /// Tab separated values with a header row and a column per field (in the order that the fields first appear in).
+    Tsv,
+}
+
+/// How a found element relates to the target that it was found for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Match {
+    /// The element matches the target.
+    Exact,
+    /// The element doesn't match the target but was accepted as the result via snapping.
+    Snap,
+}
+
+impl Match {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Match::Exact => "exact",
+            Match::Snap => "snap",
+        }
+    }
+}
+
+pub type Side = Option<(Element<Data>, Match)>;
+
+pub fn print_result(format: Format, lower: Side, upper: Side, probes: usize) -> Result<()> {
+    match format {
+        Format::Text => {
+            print_text_side("LOWER", lower);
+            print_text_side("UPPER", upper);
+        }
+        Format::Json => {
+            let res = json!({
+                "lower": json_side(lower),
+                "upper": json_side(upper),
+                "probes": probes,
+            });
+            println!("{}", serde_json::to_string(&res)?);
+        }
+        Format::Tsv => {
+            let names = tsv_field_names([&lower, &upper].into_iter().flatten().map(|(e, _)| &e.val));
+            println!("side\tindex\tmatch\tprobes{}", tsv_header(&names));
+            println!("{}", tsv_side("lower", &lower, probes, &names));
+            println!("{}", tsv_side("upper", &upper, probes, &names));
+        }
+    }
+    Ok(())
+}
+
+/// Prints the records in the index range `idxs`,
+/// marking the ones outside of the matched range `range` as context.
+/// The text format prints the input lines as they are.
+pub fn print_range(format: Format, lines: &[String], datas: &[Data], idxs: Range<usize>, range: Range<usize>) -> Result<()> {
+    let mut out = BufWriter::new(stdout().lock());
+    let names = match format {
+        Format::Tsv => tsv_field_names(datas[idxs.clone()].iter()),
+        _ => Vec::new(),
+    };
+    if let Format::Tsv = format {
+        writeln!(out, "index\tcontext{}", tsv_header(&names))?;
+    }
+    for idx in idxs {
+        let context = !range.contains(&idx);
+        match format {
+            Format::Text => writeln!(out, "{}", lines[idx])?,
+            Format::Json => writeln!(out, "{}", serde_json::to_string(&json_range_record(idx, context, &datas[idx]))?)?,
+            Format::Tsv => writeln!(out, "{}\t{}{}", idx, context, tsv_fields(&datas[idx], &names))?,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Prints the number of records in a range.
+pub fn print_count(format: Format, count: usize) -> Result<()> {
+    match format {
+        Format::Text => println!("{}", count),
+        Format::Json => println!("{}", serde_json::to_string(&json!({ "count": count }))?),
+        Format::Tsv => println!("count\n{}", count),
+    }
+    Ok(())
+}
+
+fn print_text_side(name: &str, s: Side) {
+    if let Some((Element { val, idx }, _)) = s {
+        println!("{}: index {}: {:?}", name, idx, data_to_string(&val));
+    } else {
+        println!("{}: none!", name);
+    }
+}
+
+fn json_side(s: Side) -> Value {
+    match s {
+        None => Value::Null,
+        Some((Element { val, idx }, m)) => json!({
+            "index": idx,
+            "match": m.as_str(),
+            "record": json_record(&val),
+        }),
+    }
+}
+
+fn json_range_record(idx: usize, context: bool, d: &Data) -> Value {
+    json!({
+        "index": idx,
+        "context": context,
+        "record": json_record(d),
+    })
+}
+
+fn json_record(d: &Data) -> Value {
+    Value::Object(d.fields().iter().map(|f| (f.name.clone(), json_value(f))).collect::<Map<_, _>>())
+}
+
+/// Converts numbers to JSON numbers (or `null` if they aren't finite)
+/// and all other values to the text that they were parsed from.
+/// Numbers are written as in the input if that's valid JSON, which keeps integers of any size exact.
+fn json_value(f: &Field) -> Value {
+    let number = || serde_json::from_str::<Number>(&f.text).ok().map(Value::Number);
+    match f.value {
+        DataValue::Int(v) => number().or_else(|| Number::from_i128(v).map(Value::Number)).unwrap_or(Value::Null),
+        DataValue::Float(v) if v.is_finite() => number().unwrap_or_else(|| json!(v)),
+        DataValue::Float(_) => Value::Null,
+        _ => json!(f.text),
+    }
+}
+
+/// Collects the names of the fields of the records in the order that they first appear in,
+/// which are the columns of the fields in TSV output.
+fn tsv_field_names<'a>(ds: impl Iterator<Item = &'a Data>) -> Vec<&'a str> {
+    let mut res: Vec<&str> = Vec::new();
+    for f in ds.flat_map(|d| d.fields()) {
+        if !res.contains(&f.name.as_str()) {
+            res.push(&f.name);
+        }
+    }
+    res
+}
+
+/// Formats the names of the field columns, each preceded by a tab.
+fn tsv_header(names: &[&str]) -> String {
+    names.iter().map(|n| format!("\t{}", n)).collect()
+}
+
+/// Formats the values of the record in the columns of the given fields, each preceded by a tab.
+/// The columns of fields that the record doesn't have are empty.
+fn tsv_fields(d: &Data, names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|&n| format!("\t{}", d.fields().iter().find(|f| f.name == n).map_or("", |f| f.text.as_str())))
+        .collect()
+}
+
+/// Formats a TSV row of a side, with its fields in the columns of the given fields.
+fn tsv_side(name: &str, s: &Side, probes: usize, names: &[&str]) -> String {
+    match s {
+        None => format!("{}\t\tnone\t{}{}", name, probes, "\t".repeat(names.len())),
+        Some((Element { val, idx }, m)) => format!("{}\t{}\t{}\t{}{}", name, idx, m.as_str(), probes, tsv_fields(val, names)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fields, split_stdin_record};
+    use crate::value::{Type, Types};
+
+    fn data(s: &str, types: &[(&str, Type)]) -> Data {
+        let types: Types = types.iter().map(|(k, t)| (k.to_string(), *t)).collect();
+        parse_fields(split_stdin_record(s).unwrap(), &types).unwrap()
+    }
+
+    #[test]
+    fn versions_are_printed_as_in_input() {
+        let d = data("v=v1.0.0+b1 w=1.2.3-rc.1", &[("v", Type::Version), ("w", Type::Version)]);
+        assert_eq!(tsv_fields(&d, &["v", "w"]), "\tv1.0.0+b1\t1.2.3-rc.1");
+        assert_eq!(data_to_string(&d), "v=v1.0.0+b1, w=1.2.3-rc.1");
+        assert_eq!(
+            json_side(Some((Element { val: d, idx: 0 }, Match::Exact))),
+            json!({"index": 0, "match": "exact", "record": {"v": "v1.0.0+b1", "w": "1.2.3-rc.1"}}),
+        );
+    }
+
+    #[test]
+    fn text_sorts_fields_by_name() {
+        let d = data("b=1 c=x a=2.5", &[]);
+        assert_eq!(data_to_string(&d), "a=2.5, b=1, c=x");
+    }
+
+    #[test]
+    fn json_side_shape() {
+        let d = data("b=1 a=2.5 c=x d=90m e=nan f=inf g=-inf", &[("e", Type::Float), ("f", Type::Float), ("g", Type::Float)]);
+        let res = json_side(Some((Element { val: d, idx: 3 }, Match::Snap)));
+        assert_eq!(
+            res,
+            json!({
+                "index": 3,
+                "match": "snap",
+                "record": {"b": 1, "a": 2.5, "c": "x", "d": "90m", "e": null, "f": null, "g": null},
+            }),
+        );
+        // Fields are kept in input order.
+        assert_eq!(serde_json::to_string(&res["record"]).unwrap(), r#"{"b":1,"a":2.5,"c":"x","d":"90m","e":null,"f":null,"g":null}"#);
+        assert_eq!(json_side(None), Value::Null);
+    }
+
+    #[test]
+    fn json_keeps_numbers_as_in_input() {
+        let d = data("id=18446744073709551002 n=-170141183460469231731687303715884105728 x=1.50 y=+1 z=.5", &[]);
+        assert_eq!(
+            serde_json::to_string(&json_record(&d)).unwrap(),
+            r#"{"id":18446744073709551002,"n":-170141183460469231731687303715884105728,"x":1.50,"y":1,"z":0.5}"#,
+        );
+    }
+
+    #[test]
+    fn json_range_record_shape() {
+        let d = data("b=1 a=nan", &[("a", Type::Float)]);
+        assert_eq!(json_range_record(2, true, &d), json!({"index": 2, "context": true, "record": {"b": 1, "a": null}}));
+        assert_eq!(json_range_record(5, false, &d), json!({"index": 5, "context": false, "record": {"b": 1, "a": null}}));
+    }
+
+    #[test]
+    fn tsv_side_shape() {
+        let lower = data("b=1 a=2.5 d=90m e=nan", &[("e", Type::Float)]);
+        let upper = data("a=3 c=x", &[]);
+        let names = tsv_field_names([&lower, &upper].into_iter());
+        assert_eq!(names, vec!["b", "a", "d", "e", "c"]);
+        assert_eq!(tsv_header(&names), "\tb\ta\td\te\tc");
+        assert_eq!(tsv_side("lower", &Some((Element { val: lower.clone(), idx: 3 }, Match::Snap)), 7, &names), "lower\t3\tsnap\t7\t1\t2.5\t90m\tnan\t");
+        assert_eq!(tsv_side("upper", &Some((Element { val: upper.clone(), idx: 5 }, Match::Exact)), 7, &names), "upper\t5\texact\t7\t\t3\t\t\tx");
+        assert_eq!(tsv_side("lower", &None, 7, &names), "lower\t\tnone\t7\t\t\t\t\t");
+        assert_eq!(tsv_side("lower", &None, 7, &[]), "lower\t\tnone\t7");
+    }
+}
@@ -2,12 +2,52 @@ use anyhow::{anyhow, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Op {
+    /// `=`: Inclusive bound (or plain assignment in records).
     Equals,
+    /// `~`: Bound that snaps outwards to the nearest value outside the range if there isn't an exact match.
     Tilde,
+    /// `<`: Exclusive upper bound.
+    Less,
+    /// `<=`: Inclusive upper bound.
+    LessOrEqual,
+    /// `>`: Exclusive lower bound.
+    Greater,
+    /// `>=`: Inclusive lower bound.
+    GreaterOrEqual,
+    /// `==`: Exact match (i.e. both an inclusive lower and upper bound without snapping).
+    Matches,
 }
 
+impl Op {
+    pub fn parse(s: &str) -> Result<Op> {
+        match s {
+            "=" => Ok(Op::Equals),
+            "~" => Ok(Op::Tilde),
+            "<" => Ok(Op::Less),
+            "<=" => Ok(Op::LessOrEqual),
+            ">" => Ok(Op::Greater),
+            ">=" => Ok(Op::GreaterOrEqual),
+            "==" => Ok(Op::Matches),
+            x => Err(anyhow!("invalid op '{}'", x)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Op::Equals => "=",
+            Op::Tilde => "~",
+            Op::Less => "<",
+            Op::LessOrEqual => "<=",
+            Op::Greater => ">",
+            Op::GreaterOrEqual => ">=",
+            Op::Matches => "==",
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Pair {
     pub name: String,
     pub op: Op,
@@ -17,17 +57,13 @@ pub struct Pair {
 impl Pair {
     pub fn parse(s: &str) -> Result<Pair> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"^(\w+)([=~])(.+)$").unwrap();
+            static ref RE: Regex = Regex::new(r"^(\w+)(<=|>=|==|<|>|=|~)(.+)$").unwrap();
         }
         match RE.captures(s) {
             None => Err(anyhow!("invalid pair '{}'", s)),
             Some(c) => Ok(Pair {
                 name: c[1].to_string(),
-                op: match c[2].to_string().as_str() {
-                    "=" => Op::Equals,
-                    "~" => Op::Tilde,
-                    x => return Err(anyhow!("invalid op '{}'", x)),
-                },
+                op: Op::parse(&c[2])?,
                 value: c[3].to_string(),
             }),
         }
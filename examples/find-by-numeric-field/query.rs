@@ -0,0 +1,299 @@
+use crate::pair::{Op, Pair};
+use crate::target::{BoxedTarget, DataTarget, Equality, Target};
+use crate::value::Types;
+use anyhow::{anyhow, Result};
+use b_section::combine::{FindOrdCombineLower, FindOrdCombineUpper};
+use b_section::float::Tolerance;
+use b_section::snap::SnapPolicy;
+use std::fmt::{Display, Formatter};
+
+/// Boolean expression of constraints.
+#[derive(Clone)]
+pub enum Expr {
+    Cmp(Pair),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression like `(a>=1 and b>2) or a>=3`.
+    /// The operator `and` binds tighter than `or`.
+    pub fn parse(s: &str) -> Result<Expr> {
+        let tokens = tokenize(s);
+        let mut p = Parser { tokens: &tokens, pos: 0 };
+        let e = p.parse_or()?;
+        match p.peek() {
+            None => Ok(e),
+            Some(t) => Err(anyhow!("unexpected token '{}' in query", t)),
+        }
+    }
+
+    /// Determines the bound that the expression describes.
+    /// This is only well-defined if all the constraints in the expression bound values from the same side:
+    /// Then the set of matching records is monotone (i.e. bisectable) in the expression as a whole.
+    /// The result is `None` if the expression isn't monotone.
+    fn bound(&self) -> Option<Bound> {
+        match self {
+            Expr::Cmp(p) => match p.op {
+                Op::Greater | Op::GreaterOrEqual => Some(Bound::Lower),
+                Op::Less | Op::LessOrEqual => Some(Bound::Upper),
+                // '==' is both a lower and an upper bound, so it doesn't have a single side.
+                _ => None,
+            },
+            Expr::And(es) | Expr::Or(es) => {
+                let bs = es.iter().map(Expr::bound).collect::<Option<Vec<_>>>()?;
+                if bs.iter().all(|&b| b == bs[0]) {
+                    Some(bs[0])
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Compiles the expression into combined targets for the lower and upper bounds of the matching range.
+    ///
+    /// The expression must be a conjunction (at the top level) of terms that are each monotone.
+    /// A term consisting of an '==' constraint contributes to both bounds; all other terms contribute to one of them.
+    /// Within a term, 'and' and 'or' compile into the combined targets that correspond to these operations
+    /// for the bound in question.
+    pub fn compile(self, types: &Types, epsilon: Option<Tolerance>, equality: Equality) -> Result<(Vec<BoxedTarget>, Vec<BoxedTarget>)> {
+        let terms = match self {
+            Expr::And(es) => es,
+            e => vec![e],
+        };
+        let mut lower: Vec<BoxedTarget> = Vec::new();
+        let mut upper: Vec<BoxedTarget> = Vec::new();
+        for term in terms {
+            match term {
+                Expr::Cmp(p) if p.op == Op::Matches => {
+                    lower.push(Box::new(DataTarget::from_pair(p.clone(), Target::Lower, types, epsilon, equality)?));
+                    upper.push(Box::new(DataTarget::from_pair(p, Target::Upper, types, epsilon, equality)?));
+                }
+                Expr::Cmp(p) if p.op == Op::Equals || p.op == Op::Tilde => {
+                    return Err(anyhow!("operator '{}' of constraint '{}' doesn't determine a bound: use one of '<', '<=', '>', '>=', or '=='", p.op.as_str(), p.name));
+                }
+                term => match term.bound() {
+                    Some(Bound::Lower) => lower.push(term.compile_bound(Target::Lower, types, epsilon, equality)?),
+                    Some(Bound::Upper) => upper.push(term.compile_bound(Target::Upper, types, epsilon, equality)?),
+                    None => return Err(anyhow!("query term '{}' is not monotone: all of its constraints must bound the same side using the operators '<' and '<=' or '>' and '>='", term)),
+                },
+            }
+        }
+        Ok((lower, upper))
+    }
+
+    /// Compiles the expression into a target for the given bound.
+    /// The combined targets don't snap by themselves, so the snapping within them is that of the individual constraints.
+    fn compile_bound(self, t: Target, types: &Types, epsilon: Option<Tolerance>, equality: Equality) -> Result<BoxedTarget> {
+        let compile_all = |es: Vec<Expr>| {
+            es.into_iter()
+                .map(|e| e.compile_bound(t.clone(), types, epsilon, equality))
+                .collect::<Result<Vec<_>>>()
+        };
+        let snap = SnapPolicy::None;
+        Ok(
+            match (self, &t) {
+                (Expr::Cmp(p), _) => Box::new(DataTarget::from_pair(p, t, types, epsilon, equality)?),
+                // AND of lower bounds: value must be above all targets.
+                (Expr::And(es), Target::Lower) => Box::new(FindOrdCombineUpper { combined: compile_all(es)?, snap }),
+                // OR of lower bounds: value must be above any target.
+                (Expr::Or(es), Target::Lower) => Box::new(FindOrdCombineLower { combined: compile_all(es)?, snap }),
+                // AND of upper bounds: value must be below all targets.
+                (Expr::And(es), Target::Upper) => Box::new(FindOrdCombineLower { combined: compile_all(es)?, snap }),
+                // OR of upper bounds: value must be below any target.
+                (Expr::Or(es), Target::Upper) => Box::new(FindOrdCombineUpper { combined: compile_all(es)?, snap }),
+            }
+        )
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (es, op) = match self {
+            Expr::Cmp(p) => return write!(f, "{}{}{}", p.name, p.op.as_str(), p.value),
+            Expr::And(es) => (es, " and "),
+            Expr::Or(es) => (es, " or "),
+        };
+        let es = es.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "({})", es.join(op))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bound {
+    Lower,
+    Upper,
+}
+
+/// Characters that operators consist of.
+const OP_CHARS: &str = "<>=~";
+
+/// Splits the string into parentheses, operators, and words separated by whitespace,
+/// so that constraints may be written with or without spaces around their operators.
+fn tokenize(s: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut word = String::new();
+    for c in s.chars() {
+        let is_op = OP_CHARS.contains(c);
+        // A word ends at whitespace, parentheses, and where operator characters start or stop.
+        if !word.is_empty() && (c.is_whitespace() || c == '(' || c == ')' || word.chars().all(|w| OP_CHARS.contains(w)) != is_op) {
+            res.push(std::mem::take(&mut word));
+        }
+        if c == '(' || c == ')' {
+            res.push(c.to_string());
+        } else if !c.is_whitespace() {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        res.push(word);
+    }
+    res
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        let res = self.tokens.get(self.pos).map(|t| t.as_str());
+        self.pos += 1;
+        res
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut es = vec![self.parse_and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+            self.next();
+            es.push(self.parse_and()?);
+        }
+        Ok(if es.len() == 1 { es.remove(0) } else { Expr::Or(es) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut es = vec![self.parse_atom()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("and")) {
+            self.next();
+            es.push(self.parse_atom()?);
+        }
+        Ok(if es.len() == 1 { es.remove(0) } else { Expr::And(es) })
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            None => Err(anyhow!("unexpected end of query")),
+            Some("(") => {
+                let e = self.parse_or()?;
+                match self.next() {
+                    Some(")") => Ok(e),
+                    _ => Err(anyhow!("missing ')' in query")),
+                }
+            }
+            Some(name) => {
+                let name = name.to_string();
+                if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(anyhow!("invalid field name '{}' in query", name));
+                }
+                let op = match self.next() {
+                    None => return Err(anyhow!("unexpected end of query")),
+                    Some(op) => Op::parse(op)?,
+                };
+                let value = match self.next() {
+                    None => return Err(anyhow!("unexpected end of query")),
+                    Some(v) if v == "(" || v == ")" || v.chars().any(|c| OP_CHARS.contains(c)) => {
+                        return Err(anyhow!("invalid value '{}' of constraint '{}' in query", v, name))
+                    }
+                    Some(v) => v.to_string(),
+                };
+                Ok(Expr::Cmp(Pair { name, op, value }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fields, split_stdin_record};
+    use crate::target::Data;
+    use b_section::find::find;
+
+    fn parse(s: &str) -> String {
+        Expr::parse(s).unwrap().to_string()
+    }
+
+    fn compile(s: &str) -> Result<(Vec<BoxedTarget>, Vec<BoxedTarget>)> {
+        Expr::parse(s)?.compile(&Types::new(), None, Equality::Bound)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parse("a>=1 or b>=2 and c>=3"), "(a>=1 or (b>=2 and c>=3))");
+        assert_eq!(parse("a>=1 and b>=2 or c>=3"), "((a>=1 and b>=2) or c>=3)");
+        assert_eq!(parse("a>=1 AND b>=2 OR c>=3"), "((a>=1 and b>=2) or c>=3)");
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        assert_eq!(parse("(a>=1 or b>=2) and c>=3"), "((a>=1 or b>=2) and c>=3)");
+        assert_eq!(parse("a>=1 and (b>=2 or c>=3)"), "(a>=1 and (b>=2 or c>=3))");
+        assert_eq!(parse("((a>=1))"), "a>=1");
+    }
+
+    #[test]
+    fn whitespace_around_operators_is_optional() {
+        for s in ["id>=2 and id<=3", "id >= 2 and id <= 3", "id >=2 and id<=3", "id>= 2 and id<=3", "(id>=2)and(id<=3)", "  id>=2  and\tid<=3  "] {
+            assert_eq!(parse(s), "(id>=2 and id<=3)", "query '{}'", s);
+        }
+        assert_eq!(parse("id >=2"), "id>=2");
+        assert_eq!(parse("id>=-2"), "id>=-2");
+        assert_eq!(parse("t >= 5±1"), "t>=5±1");
+    }
+
+    #[test]
+    fn rejects_invalid_syntax() {
+        for s in ["", "a>=1 and", "a>=1 or", "(a>=1", "a>=1)", "a>=", "a", "a>=1 b>=2", ">=1", "a >= >= 1", "a => 1", "a>=1 and (b)"] {
+            assert!(Expr::parse(s).is_err(), "query '{}'", s);
+        }
+    }
+
+    #[test]
+    fn compiles_terms_into_bounds() {
+        let (lower, upper) = compile("a>=1 and b<=2").unwrap();
+        assert_eq!((lower.len(), upper.len()), (1, 1));
+        let (lower, upper) = compile("(a>=1 or b>2) and c<=3 and d<4").unwrap();
+        assert_eq!((lower.len(), upper.len()), (1, 2));
+        let (lower, upper) = compile("a>=1 or b>=2").unwrap();
+        assert_eq!((lower.len(), upper.len()), (1, 0));
+    }
+
+    #[test]
+    fn splits_matches_into_both_bounds() {
+        let (lower, upper) = compile("id==2").unwrap();
+        assert_eq!((lower.len(), upper.len()), (1, 1));
+        let (lower, upper) = compile("id==2 and x>=1").unwrap();
+        assert_eq!((lower.len(), upper.len()), (2, 1));
+
+        let datas: Vec<Data> = (0..5).map(|i| parse_fields(split_stdin_record(&format!("id={}", i)).unwrap(), &Types::new()).unwrap()).collect();
+        let lookup = crate::new_lookup(&datas);
+        let (lower, upper) = compile("id==2").unwrap();
+        assert_eq!(find(&lookup, lower[0].as_ref(), 0, 4).unwrap().element.map(|e| e.idx), Some(2));
+        assert_eq!(find(&lookup, upper[0].as_ref(), 0, 4).unwrap().element.map(|e| e.idx), Some(2));
+    }
+
+    #[test]
+    fn rejects_non_monotone_terms() {
+        assert!(compile("a>=1 or b<=2").is_err());
+        assert!(compile("(a>=1 and b<=2) or c>=3").is_err());
+        assert!(compile("a==1 or b>=2").is_err());
+        assert!(compile("a=1").is_err());
+        assert!(compile("a~1").is_err());
+    }
+}
@@ -1,67 +1,257 @@
 use crate::pair::{Op, Pair};
+use crate::value::{Type, Types, Value};
 use anyhow::{anyhow, Error};
 use b_section::find::{FindOrd, FindOrdering};
-use std::collections::HashMap;
+use b_section::float::Tolerance;
+use b_section::op::Op as FindOp;
+use b_section::snap::SnapPolicy;
+use std::cmp::Ordering;
 
-pub type Data = HashMap<String, f64>;
+/// Field of a record along with the text that its value was parsed from.
+/// The text is used for printing the field, so that it's printed exactly like it appeared in the input.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    pub value: Value,
+    pub text: String,
+}
 
-fn sorted_items<K: Ord, V>(m: &HashMap<K, V>) -> Vec<(&K, &V)> {
-    let mut res: Vec<_> = m.iter().collect();
-    res.sort_by(|&(l, _), &(r, _)| l.cmp(r));
-    res
+/// Record of named fields, kept in the order that they were parsed in.
+#[derive(Clone, Debug)]
+pub struct Data {
+    fields: Vec<Field>,
 }
 
-pub fn data_to_string(d: Data) -> String {
-    sorted_items(&d)
+impl Data {
+    pub fn new(fields: Vec<Field>) -> Result<Data, Error> {
+        for (i, f) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|l| l.name == f.name) {
+                return Err(anyhow!("duplicate key '{}'", f.name));
+            }
+        }
+        Ok(Data { fields })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|f| f.name == name).map(|f| &f.value)
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+}
+
+/// Formats the fields sorted by their names.
+pub fn data_to_string(d: &Data) -> String {
+    let mut fields: Vec<_> = d.fields().iter().collect();
+    fields.sort_by_key(|f| &f.name);
+    fields
         .iter()
-        .map(|&(k, v)| format!("{k}={v}"))
+        .map(|f| format!("{}={}", f.name, f.text))
         .collect::<Vec<_>>()
         .join(", ")
 }
 
+/// Determines how a value that is equal to the target value compares against the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnEqual {
+    /// The value matches the target, which stops the search at the first equal value that it probes.
+    Match,
+    /// The value is compared like [`FindOp::find_ordering`] does for the operator:
+    /// For the inclusive bounds ([`FindOp::Ge`] and [`FindOp::Le`]), the value is a valid result,
+    /// but the search continues in order to find the outermost one of any duplicates.
+    /// For the exclusive bounds ([`FindOp::Gt`] and [`FindOp::Lt`]), the value is outside of the bound.
+    Bound(FindOp),
+}
+
+/// Determines whether targets find the outermost of duplicate values or just any of them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Equality {
+    /// Values equal to a lower (upper) bound are valid results, but the search continues to find the first (last) one.
+    Bound,
+    /// Values equal to a bound match it.
+    /// This is used for checking whether a found record matches the constraints exactly rather than by snapping.
+    Match,
+}
+
 pub struct DataTarget {
     pub name: String,
-    pub val: f64,
-    pub snap_downwards: bool,
-    pub snap_upwards: bool,
+    pub val: Value,
+    pub snap: SnapPolicy,
+    /// Maximum distance of values that the target snaps to.
+    pub tolerance: Option<Value>,
+    /// Tolerance within which floats are considered equal to the target value.
+    pub epsilon: Option<Tolerance>,
+    pub on_equal: OnEqual,
 }
 
 impl FindOrd<Data, Error> for DataTarget {
     fn cmp(&self, t: &Data) -> Result<FindOrdering, Error> {
         match t.get(&self.name) {
             None => Err(anyhow!("missing key '{}'", self.name)),
-            Some(&val) => Ok(
-                if self.val < val {
-                    FindOrdering::ValAboveTarget { is_valid_res: self.snap_upwards }
-                } else if self.val > val {
-                    FindOrdering::ValBelowTarget { is_valid_res: self.snap_downwards }
-                } else {
-                    FindOrdering::ValMatchesTarget
-                }
-            )
+            Some(val) => {
+                let snap = self.snap_for(val)?;
+                Ok(
+                    match self.compare(val)? {
+                        Some(Ordering::Less) => FindOrdering::above(snap),
+                        Some(Ordering::Greater) => FindOrdering::below(snap),
+                        Some(Ordering::Equal) => match self.on_equal {
+                            OnEqual::Match => FindOrdering::ValMatchesTarget,
+                            OnEqual::Bound(op) => op.find_ordering(Some(Ordering::Equal), snap),
+                        },
+                        None => FindOrdering::ValIncomparable,
+                    }
+                )
+            }
+        }
+    }
+
+    /// Prefers the value that is equal to the target, if any, or otherwise the one below.
+    /// Only '~' bounds find valid results on both sides: an equal value on the inner side and a snapped one on the outer side.
+    fn prefer_snap_downwards(&self, _below: &Data, above: &Data) -> Result<bool, Error> {
+        match above.get(&self.name) {
+            None => Err(anyhow!("missing key '{}'", self.name)),
+            Some(val) => Ok(self.compare(val)? != Some(Ordering::Equal)),
         }
     }
 }
 
 impl DataTarget {
-    pub fn from_pair(p: Pair, t: Target) -> Result<DataTarget, Error> {
+    /// Parses the target from a constraint whose value may be followed by a tolerance as in 'value±tolerance'.
+    pub fn from_pair(p: Pair, t: Target, types: &Types, epsilon: Option<Tolerance>, equality: Equality) -> Result<DataTarget, Error> {
+        let (val, tolerance) = match p.value.split_once('±') {
+            None => (p.value.as_str(), None),
+            Some((v, t)) => (v, Some(t)),
+        };
+        let val = Value::parse(val, types.get(&p.name).copied())?;
+        let tolerance = tolerance.map(|t| parse_tolerance(t, &val)).transpose()?;
         let name = p.name;
-        let val = p.value.parse()?;
-        let snap_out = match p.op {
-            Op::Equals => false,
-            Op::Tilde => true,
+        let inclusive = |op| match equality {
+            Equality::Bound => OnEqual::Bound(op),
+            Equality::Match => OnEqual::Match,
         };
-        let snap_upwards = match t {
-            Target::Lower => !snap_out,
-            Target::Upper => snap_out,
+        let (snap, on_equal) = match (p.op, &t) {
+            (Op::Equals | Op::GreaterOrEqual, Target::Lower) => (SnapPolicy::Up, inclusive(FindOp::Ge)),
+            (Op::Greater, Target::Lower) => (SnapPolicy::Up, OnEqual::Bound(FindOp::Gt)),
+            (Op::Equals | Op::LessOrEqual, Target::Upper) => (SnapPolicy::Down, inclusive(FindOp::Le)),
+            (Op::Less, Target::Upper) => (SnapPolicy::Down, OnEqual::Bound(FindOp::Lt)),
+            (Op::Tilde, Target::Lower) => (SnapPolicy::Down, inclusive(FindOp::Ge)),
+            (Op::Tilde, Target::Upper) => (SnapPolicy::Up, inclusive(FindOp::Le)),
+            (Op::Matches, Target::Lower) => (SnapPolicy::None, inclusive(FindOp::Ge)),
+            (Op::Matches, Target::Upper) => (SnapPolicy::None, inclusive(FindOp::Le)),
+            (op, Target::Lower) => return Err(anyhow!("operator '{}' cannot be used for a lower bound", op.as_str())),
+            (op, Target::Upper) => return Err(anyhow!("operator '{}' cannot be used for an upper bound", op.as_str())),
         };
-        let snap_downwards = !snap_upwards;
-        Ok(DataTarget { name, val, snap_downwards, snap_upwards })
+        if tolerance.is_some() && snap == SnapPolicy::None {
+            return Err(anyhow!("operator '{}' doesn't snap, so it cannot have a tolerance", p.op.as_str()));
+        }
+        Ok(DataTarget { name, val, snap, tolerance, epsilon, on_equal })
+    }
+
+    /// Compares the target value against the given value,
+    /// considering them equal if at least one of them is a float and they're equal within `epsilon`.
+    fn compare(&self, val: &Value) -> Result<Option<Ordering>, Error> {
+        let floats = match (&self.val, val) {
+            (Value::Float(l), Value::Float(r)) => Some((*l, *r)),
+            (Value::Int(l), Value::Float(r)) => Some((*l as f64, *r)),
+            (Value::Float(l), Value::Int(r)) => Some((*l, *r as f64)),
+            _ => None,
+        };
+        if let (Some(eps), Some((l, r))) = (&self.epsilon, floats) {
+            if eps.eq(l, r) {
+                return Ok(Some(Ordering::Equal));
+            }
+        }
+        self.val.compare(val)
+    }
+
+    /// Determines the snapping for the given value, which is disabled if it's further away than the tolerance.
+    fn snap_for(&self, val: &Value) -> Result<SnapPolicy, Error> {
+        if let Some(t) = &self.tolerance {
+            if self.val.distance(val)?.compare(t)? == Some(Ordering::Greater) {
+                return Ok(SnapPolicy::None);
+            }
+        }
+        Ok(self.snap)
     }
 }
 
+/// Parses a tolerance for the given target value:
+/// Timestamps and durations have tolerances that are durations, and numbers have numeric tolerances.
+fn parse_tolerance(s: &str, val: &Value) -> Result<Value, Error> {
+    match val {
+        Value::Timestamp(_) | Value::Duration(_) => Value::parse(s, Some(Type::Duration)),
+        Value::Int(_) | Value::Float(_) => Value::parse(s, Some(Type::Int)).or_else(|_| Value::parse(s, Some(Type::Float))),
+        Value::Str(_) | Value::Natural(_) | Value::Version(_) => Err(anyhow!("{} '{}' cannot have a tolerance", val.type_name(), val)),
+    }
+}
+
+pub type BoxedTarget = Box<dyn FindOrd<Data, Error>>;
+
 #[derive(Clone)]
 pub enum Target {
     Lower,
     Upper,
 }
+
+impl Target {
+    /// Determines which bound(s) a constraint using the given operator describes on its own.
+    /// The operators '=' and '~' depend on the bound that they're used for, so they don't describe any bounds by themselves.
+    pub fn from_op(op: Op) -> Vec<Target> {
+        match op {
+            Op::Equals | Op::Tilde => vec![],
+            Op::Greater | Op::GreaterOrEqual => vec![Target::Lower],
+            Op::Less | Op::LessOrEqual => vec![Target::Upper],
+            Op::Matches => vec![Target::Lower, Target::Upper],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fields, split_stdin_record};
+    use b_section::find::find;
+
+    fn find_idx(datas: &[Data], constraint: &str, t: Target) -> Option<i64> {
+        let target = DataTarget::from_pair(Pair::parse(constraint).unwrap(), t, &Types::new(), None, Equality::Bound).unwrap();
+        find(&crate::new_lookup(datas), &target, 0, datas.len() as i64 - 1).unwrap().element.map(|e| e.idx)
+    }
+
+    #[test]
+    fn bounds_find_outermost_duplicate() {
+        let datas: Vec<Data> =
+            ["a=1", "a=2", "a=2", "a=2", "a=2", "a=2", "a=2", "a=3"]
+                .iter()
+                .map(|s| parse_fields(split_stdin_record(s).unwrap(), &Types::new()).unwrap())
+                .collect();
+        assert_eq!(find_idx(&datas, "a>=2", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a=2", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a==2", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a~2", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a>1", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a>2", Target::Lower), Some(7));
+        assert_eq!(find_idx(&datas, "a<=2", Target::Upper), Some(6));
+        assert_eq!(find_idx(&datas, "a=2", Target::Upper), Some(6));
+        assert_eq!(find_idx(&datas, "a==2", Target::Upper), Some(6));
+        assert_eq!(find_idx(&datas, "a~2", Target::Upper), Some(6));
+        assert_eq!(find_idx(&datas, "a<3", Target::Upper), Some(6));
+        assert_eq!(find_idx(&datas, "a<2", Target::Upper), Some(0));
+    }
+
+    #[test]
+    fn bounds_snap_past_missing_value() {
+        let datas: Vec<Data> =
+            ["a=1", "a=1", "a=3", "a=3"]
+                .iter()
+                .map(|s| parse_fields(split_stdin_record(s).unwrap(), &Types::new()).unwrap())
+                .collect();
+        assert_eq!(find_idx(&datas, "a>=2", Target::Lower), Some(2));
+        assert_eq!(find_idx(&datas, "a<=2", Target::Upper), Some(1));
+        assert_eq!(find_idx(&datas, "a==2", Target::Lower), None);
+        assert_eq!(find_idx(&datas, "a==2", Target::Upper), None);
+        // '~' snaps outwards.
+        assert_eq!(find_idx(&datas, "a~2", Target::Lower), Some(1));
+        assert_eq!(find_idx(&datas, "a~2", Target::Upper), Some(2));
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::{anyhow, Result};
+use b_section::collate::{natural_cmp, Version};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Type of the values of a field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Str,
+    /// RFC 3339 timestamp (e.g. `2024-01-02T10:00:00Z`).
+    Timestamp,
+    /// Human readable duration (e.g. `1h30m` or `250ms`).
+    Duration,
+    /// String compared in natural order (e.g. `build-9` < `build-10`).
+    Natural,
+    /// Semantic version (e.g. `1.2.3-rc.1`).
+    Version,
+}
+
+impl FromStr for Type {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Type> {
+        match s {
+            "int" => Ok(Type::Int),
+            "float" => Ok(Type::Float),
+            "string" => Ok(Type::Str),
+            "timestamp" => Ok(Type::Timestamp),
+            "duration" => Ok(Type::Duration),
+            "natural" => Ok(Type::Natural),
+            "semver" => Ok(Type::Version),
+            x => Err(anyhow!("invalid type '{}'", x)),
+        }
+    }
+}
+
+/// Explicitly specified types of fields.
+/// The types of values of fields not present in the map are inferred from the values themselves.
+pub type Types = HashMap<String, Type>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Integer, which is wide enough for any 64-bit (signed or unsigned) integer.
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Timestamp(SystemTime),
+    Duration(Duration),
+    Natural(String),
+    Version(Version),
+}
+
+impl Value {
+    /// Parses the value as the given type or, if it's `None`, as the first of
+    /// integer, float, timestamp, and duration that the value is valid for.
+    /// Values that aren't valid for any of these types are parsed as strings
+    /// (i.e. natural order and versions are never inferred).
+    /// Floats are only inferred from numeric literals, so values like `nan` or `inf` are strings unless typed as floats.
+    pub fn parse(s: &str, t: Option<Type>) -> Result<Value> {
+        match t {
+            Some(Type::Int) => Ok(Value::Int(s.parse()?)),
+            Some(Type::Float) => Ok(Value::Float(s.parse()?)),
+            Some(Type::Str) => Ok(Value::Str(s.to_string())),
+            Some(Type::Timestamp) => Ok(Value::Timestamp(humantime::parse_rfc3339_weak(s)?)),
+            Some(Type::Duration) => Ok(Value::Duration(humantime::parse_duration(s)?)),
+            Some(Type::Natural) => Ok(Value::Natural(s.to_string())),
+            Some(Type::Version) => Ok(Value::Version(s.parse()?)),
+            None => Ok(
+                [Type::Int, Type::Float, Type::Timestamp, Type::Duration]
+                    .into_iter()
+                    .find(|&t| infers_as(s, t))
+                    .map_or_else(|| Ok(Value::Str(s.to_string())), |t| Value::parse(s, Some(t)))?
+            ),
+        }
+    }
+
+    /// Compares the value against another one.
+    /// Integers and floats are compared numerically (without losing precision);
+    /// comparing values of any other pair of different types is an error.
+    /// The result is `None` if the values are incomparable (i.e. one of them is NaN).
+    pub fn compare(&self, other: &Value) -> Result<Option<Ordering>> {
+        Ok(
+            match (self, other) {
+                (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+                (Value::Float(l), Value::Float(r)) => l.partial_cmp(r),
+                (Value::Int(l), Value::Float(r)) => cmp_int_float(*l, *r),
+                (Value::Float(l), Value::Int(r)) => cmp_int_float(*r, *l).map(Ordering::reverse),
+                (Value::Str(l), Value::Str(r)) => Some(l.cmp(r)),
+                (Value::Timestamp(l), Value::Timestamp(r)) => Some(l.cmp(r)),
+                (Value::Duration(l), Value::Duration(r)) => Some(l.cmp(r)),
+                (Value::Natural(l), Value::Natural(r)) => Some(natural_cmp(l, r)),
+                (Value::Version(l), Value::Version(r)) => Some(l.cmp(r)),
+                (l, r) => return Err(anyhow!("cannot compare {} '{}' with {} '{}'", l.type_name(), l, r.type_name(), r)),
+            }
+        )
+    }
+
+    /// Computes the (absolute) distance between the value and another one.
+    /// The distance between timestamps or durations is a duration,
+    /// and between numbers it's an integer if both are integers (and the difference doesn't overflow) or a float otherwise.
+    pub fn distance(&self, other: &Value) -> Result<Value> {
+        Ok(
+            match (self, other) {
+                (Value::Int(l), Value::Int(r)) => match l.checked_sub(*r).and_then(i128::checked_abs) {
+                    Some(d) => Value::Int(d),
+                    None => Value::Float((*l as f64 - *r as f64).abs()),
+                },
+                (Value::Int(l), Value::Float(r)) | (Value::Float(r), Value::Int(l)) => Value::Float((*l as f64 - r).abs()),
+                (Value::Float(l), Value::Float(r)) => Value::Float((l - r).abs()),
+                (Value::Timestamp(l), Value::Timestamp(r)) => Value::Duration(l.duration_since(*r).unwrap_or_else(|e| e.duration())),
+                (Value::Duration(l), Value::Duration(r)) => Value::Duration(l.abs_diff(*r)),
+                (l, r) => return Err(anyhow!("cannot compute distance between {} '{}' and {} '{}'", l.type_name(), l, r.type_name(), r)),
+            }
+        )
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "string",
+            Value::Timestamp(_) => "timestamp",
+            Value::Duration(_) => "duration",
+            Value::Natural(_) => "natural",
+            Value::Version(_) => "semver",
+        }
+    }
+}
+
+/// Determines whether the type may be inferred for the value (see [`Value::parse`]).
+fn infers_as(s: &str, t: Type) -> bool {
+    (t != Type::Float || is_numeric_literal(s)) && Value::parse(s, Some(t)).is_ok()
+}
+
+/// Infers the types of the fields that aren't typed explicitly from all of their values (given as `(name, value)`),
+/// such that the values of each field are comparable with each other.
+/// The type of a field is the first of timestamp and duration that all of its values are valid for or, failing that, string.
+/// Fields whose values are all integers or floats are left untyped,
+/// as the type of each of these values is inferred on its own and they're comparable with each other.
+pub fn infer_types<'a>(fields: impl IntoIterator<Item = (&'a str, &'a str)>, types: &Types) -> Types {
+    // Candidate types for each field in order of preference, with 'None' representing a number.
+    let mut candidates: HashMap<&str, Vec<Option<Type>>> = HashMap::new();
+    for (name, value) in fields {
+        if types.contains_key(name) {
+            continue;
+        }
+        candidates
+            .entry(name)
+            .or_insert_with(|| vec![None, Some(Type::Timestamp), Some(Type::Duration)])
+            .retain(|c| match c {
+                None => infers_as(value, Type::Int) || infers_as(value, Type::Float),
+                Some(t) => infers_as(value, *t),
+            });
+    }
+    let mut res = types.clone();
+    for (name, cs) in candidates {
+        match cs.first() {
+            Some(None) => {}
+            Some(Some(t)) => {
+                res.insert(name.to_string(), *t);
+            }
+            None => {
+                res.insert(name.to_string(), Type::Str);
+            }
+        }
+    }
+    res
+}
+
+/// Determines whether the value consists only of digits, signs, decimal points, and exponent markers
+/// (as opposed to the special floats like `nan` or `inf`).
+fn is_numeric_literal(s: &str) -> bool {
+    s.chars().any(|c| c.is_ascii_digit()) && s.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+}
+
+/// Compares an integer with a float without rounding the integer (as casting it to a float would).
+fn cmp_int_float(i: i128, f: f64) -> Option<Ordering> {
+    if f.is_nan() {
+        None
+    } else if f >= 2f64.powi(127) {
+        // 'f' is at least 2^127 (which is above 'i128::MAX').
+        Some(Ordering::Less)
+    } else if f < -(2f64.powi(127)) {
+        Some(Ordering::Greater)
+    } else {
+        // 'f' is in range of 'i128', so its integer part is exact.
+        Some(i.cmp(&(f.trunc() as i128)).then(0.0.partial_cmp(&f.fract())?))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Str(v) => write!(f, "{}", v),
+            Value::Timestamp(v) => write!(f, "{}", humantime::format_rfc3339(*v)),
+            Value::Duration(v) => write!(f, "{}", humantime::format_duration(*v)),
+            Value::Natural(v) => write!(f, "{}", v),
+            Value::Version(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn infers_type_of_value() {
+        assert_eq!(Value::parse("42", None).unwrap(), Value::Int(42));
+        assert_eq!(Value::parse("-42", None).unwrap(), Value::Int(-42));
+        assert_eq!(Value::parse("18446744073709551615", None).unwrap(), Value::Int(u64::MAX as i128));
+        assert_eq!(Value::parse("4.5", None).unwrap(), Value::Float(4.5));
+        assert_eq!(Value::parse("-1e3", None).unwrap(), Value::Float(-1000.0));
+        assert_eq!(Value::parse("1970-01-01T00:00:01Z", None).unwrap(), Value::Timestamp(UNIX_EPOCH + Duration::from_secs(1)));
+        assert_eq!(Value::parse("90m", None).unwrap(), Value::Duration(Duration::from_secs(5400)));
+        assert_eq!(Value::parse("abc", None).unwrap(), Value::Str("abc".to_string()));
+        assert_eq!(Value::parse("1.2.3", None).unwrap(), Value::Str("1.2.3".to_string()));
+    }
+
+    #[test]
+    fn infers_special_floats_as_strings() {
+        for s in ["nan", "NaN", "inf", "-inf", "infinity", "Infinity"] {
+            assert_eq!(Value::parse(s, None).unwrap(), Value::Str(s.to_string()));
+        }
+    }
+
+    #[test]
+    fn parses_special_floats_as_floats_if_typed() {
+        assert_matches!(Value::parse("nan", Some(Type::Float)).unwrap(), Value::Float(v) if v.is_nan());
+        assert_eq!(Value::parse("inf", Some(Type::Float)).unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(Value::parse("-infinity", Some(Type::Float)).unwrap(), Value::Float(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn parses_explicit_type() {
+        assert_eq!(Value::parse("42", Some(Type::Float)).unwrap(), Value::Float(42.0));
+        assert_eq!(Value::parse("42", Some(Type::Str)).unwrap(), Value::Str("42".to_string()));
+        assert_eq!(Value::parse("42", Some(Type::Natural)).unwrap(), Value::Natural("42".to_string()));
+        assert!(Value::parse("4.5", Some(Type::Int)).is_err());
+        assert!(Value::parse("abc", Some(Type::Duration)).is_err());
+    }
+
+    #[test]
+    fn infers_type_per_field() {
+        let fields = [("a", "10"), ("b", "1"), ("c", "1h"), ("a", "a5"), ("b", "2.5"), ("c", "90m"), ("d", "x"), ("e", "1")];
+        let types = infer_types(fields, &[("e".to_string(), Type::Float)].into());
+        assert_eq!(types.get("a"), Some(&Type::Str));
+        assert_eq!(types.get("b"), None);
+        assert_eq!(types.get("c"), Some(&Type::Duration));
+        assert_eq!(types.get("d"), Some(&Type::Str));
+        assert_eq!(types.get("e"), Some(&Type::Float));
+        assert_eq!(Value::parse("10", types.get("a").copied()).unwrap(), Value::Str("10".to_string()));
+    }
+
+    #[test]
+    fn cmp_int_float_is_exact_beyond_float_precision() {
+        let p53 = 1i128 << 53;
+        // Casting 2^53+1 to a float rounds it to 2^53.
+        assert_eq!(cmp_int_float(p53 + 1, p53 as f64), Some(Ordering::Greater));
+        assert_eq!(cmp_int_float(p53, p53 as f64), Some(Ordering::Equal));
+        assert_eq!(cmp_int_float(p53 - 1, p53 as f64), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(-p53 - 1, -p53 as f64), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(i64::MAX as i128, i64::MAX as f64), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(i64::MIN as i128, i64::MIN as f64), Some(Ordering::Equal));
+        assert_eq!(cmp_int_float(u64::MAX as i128, u64::MAX as f64), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(i128::MAX, i128::MAX as f64), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(i128::MIN, i128::MIN as f64), Some(Ordering::Equal));
+        assert_eq!(cmp_int_float(i128::MIN, f64::NEG_INFINITY), Some(Ordering::Greater));
+        assert_eq!(cmp_int_float(i128::MAX, f64::INFINITY), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(i128::MAX, f64::MAX), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(0, f64::NAN), None);
+    }
+
+    #[test]
+    fn compares_64_bit_ids_exactly() {
+        let ids = ["18446744073709551000", "18446744073709551001", "18446744073709551002"].map(|s| Value::parse(s, None).unwrap());
+        assert_eq!(ids[2].compare(&ids[1]).unwrap(), Some(Ordering::Greater));
+        assert_eq!(ids[1].compare(&ids[2]).unwrap(), Some(Ordering::Less));
+        assert_eq!(ids[2].compare(&Value::parse("18446744073709551002", Some(Type::Int)).unwrap()).unwrap(), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn cmp_int_float_considers_fraction() {
+        assert_eq!(cmp_int_float(1, 1.5), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(2, 1.5), Some(Ordering::Greater));
+        assert_eq!(cmp_int_float(-1, -1.5), Some(Ordering::Greater));
+        assert_eq!(cmp_int_float(-2, -1.5), Some(Ordering::Less));
+        assert_eq!(cmp_int_float(0, -0.0), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn compares_values() {
+        let cmp = |l: &str, r: &str| Value::parse(l, None).unwrap().compare(&Value::parse(r, None).unwrap());
+        assert_eq!(cmp("1", "2").unwrap(), Some(Ordering::Less));
+        assert_eq!(cmp("2", "1.5").unwrap(), Some(Ordering::Greater));
+        assert_eq!(cmp("1.5", "2").unwrap(), Some(Ordering::Less));
+        assert_eq!(cmp("1.0", "1").unwrap(), Some(Ordering::Equal));
+        assert_eq!(cmp("90m", "1h").unwrap(), Some(Ordering::Greater));
+        assert_eq!(cmp("b", "a").unwrap(), Some(Ordering::Greater));
+        assert!(cmp("1", "a").is_err());
+        assert!(cmp("1", "1h").is_err());
+        assert_eq!(Value::Float(f64::NAN).compare(&Value::Int(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn computes_distance() {
+        assert_eq!(Value::Int(3).distance(&Value::Int(-2)).unwrap(), Value::Int(5));
+        assert_eq!(Value::Int(-2).distance(&Value::Int(3)).unwrap(), Value::Int(5));
+        assert_eq!(Value::Int(i64::MAX as i128).distance(&Value::Int(i64::MIN as i128)).unwrap(), Value::Int(u64::MAX as i128));
+        assert_eq!(Value::Int(i128::MAX).distance(&Value::Int(i128::MIN)).unwrap(), Value::Float(2f64.powi(128)));
+        assert_eq!(Value::Int(1).distance(&Value::Float(2.5)).unwrap(), Value::Float(1.5));
+        assert_eq!(Value::Float(2.5).distance(&Value::Int(1)).unwrap(), Value::Float(1.5));
+        assert_eq!(Value::Float(-1.0).distance(&Value::Float(1.0)).unwrap(), Value::Float(2.0));
+        let t = |s| UNIX_EPOCH + Duration::from_secs(s);
+        assert_eq!(Value::Timestamp(t(10)).distance(&Value::Timestamp(t(70))).unwrap(), Value::Duration(Duration::from_secs(60)));
+        assert_eq!(Value::Timestamp(t(70)).distance(&Value::Timestamp(t(10))).unwrap(), Value::Duration(Duration::from_secs(60)));
+        assert_eq!(Value::Duration(Duration::from_secs(1)).distance(&Value::Duration(Duration::from_secs(3))).unwrap(), Value::Duration(Duration::from_secs(2)));
+        assert!(Value::Str("a".to_string()).distance(&Value::Str("b".to_string())).is_err());
+        assert!(Value::Int(1).distance(&Value::Duration(Duration::from_secs(1))).is_err());
+    }
+}
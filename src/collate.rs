@@ -0,0 +1,293 @@
+use crate::find::{FindOrd, FindOrdering};
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Compares strings in "natural" order, where runs of ASCII digits are compared by their numeric value
+/// (such that e.g. `build-9` is less than `build-10`) and all other characters are compared by code point.
+///
+/// Numbers of any length are supported as they are compared as digit strings.
+/// Numbers that only differ by leading zeros are equal, except that if the strings are otherwise equal,
+/// the first such number with fewer leading zeros is considered less (i.e. `a1` < `a01`).
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    let mut tie = Ordering::Equal;
+    loop {
+        let (x, y) = match (a.chars().next(), b.chars().next()) {
+            (None, None) => return tie,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (da, ra) = split_digits(a);
+            let (db, rb) = split_digits(b);
+            let (na, nb) = (da.trim_start_matches('0'), db.trim_start_matches('0'));
+            let ord = Ord::cmp(&na.len(), &nb.len()).then_with(|| Ord::cmp(na, nb));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            if tie == Ordering::Equal {
+                tie = Ord::cmp(&da.len(), &db.len());
+            }
+            (a, b) = (ra, rb);
+        } else {
+            if x != y {
+                return Ord::cmp(&x, &y);
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+/// Splits the leading ASCII digits off the string.
+fn split_digits(s: &str) -> (&str, &str) {
+    s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+}
+
+/// Target that compares strings in natural order (see [`natural_cmp`]), snapping according to [`SnapPolicy`].
+#[derive(Clone, Debug)]
+pub struct NaturalTarget<S> {
+    pub value: S,
+    pub snap: SnapPolicy,
+}
+
+impl<S> NaturalTarget<S> {
+    /// Creates a target that doesn't snap.
+    pub fn new(value: S) -> Self {
+        NaturalTarget { value, snap: SnapPolicy::None }
+    }
+}
+
+impl<T: AsRef<str>, S: AsRef<str>, E> FindOrd<T, E> for NaturalTarget<S> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(Some(natural_cmp(self.value.as_ref(), t.as_ref())), self.snap))
+    }
+}
+
+/// Identifier of the pre-release part of a [`Version`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreRelease {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+impl Ord for PreRelease {
+    /// Numeric identifiers are compared numerically and have lower precedence than alphanumeric ones,
+    /// which are compared lexically.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (PreRelease::Numeric(l), PreRelease::Numeric(r)) => Ord::cmp(l, r),
+            (PreRelease::Numeric(_), PreRelease::Alphanumeric(_)) => Ordering::Less,
+            (PreRelease::Alphanumeric(_), PreRelease::Numeric(_)) => Ordering::Greater,
+            (PreRelease::Alphanumeric(l), PreRelease::Alphanumeric(r)) => Ord::cmp(l, r),
+        }
+    }
+}
+
+impl PartialOrd for PreRelease {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Semantic version (see <https://semver.org>), ordered by precedence.
+///
+/// Build metadata is ignored, i.e. it's neither stored nor compared.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Pre-release identifiers, which are compared lexicographically.
+    /// A version with pre-release identifiers has lower precedence than the same version without any.
+    pub pre: PreReleases,
+}
+
+/// Pre-release identifiers of a [`Version`].
+/// These are ordered such that the empty list (i.e. a release) is greatest.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreReleases(pub Vec<PreRelease>);
+
+impl Ord for PreReleases {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.is_empty(), other.0.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => Ord::cmp(&self.0, &other.0),
+        }
+    }
+}
+
+impl PartialOrd for PreReleases {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Error of parsing an invalid [`Version`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseVersionError(String);
+
+impl Display for ParseVersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid version '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    /// Parses a version like `1.2.3`, `1.2.3-rc.1`, or `1.2.3+build.5`.
+    /// A leading `v` (as in `v1.2.3`) is accepted as it's commonly used for tags and file names.
+    fn from_str(s: &str) -> Result<Version, ParseVersionError> {
+        let err = || ParseVersionError(s.to_string());
+        let v = s.strip_prefix('v').unwrap_or(s);
+        let v = v.split_once('+').map_or(v, |(v, _build)| v);
+        let (v, pre) = match v.split_once('-') {
+            None => (v, None),
+            Some((v, pre)) => (v, Some(pre)),
+        };
+        let number = |n: &str| {
+            if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) || (n.len() > 1 && n.starts_with('0')) {
+                return None;
+            }
+            n.parse::<u64>().ok()
+        };
+        let mut parts = v.split('.');
+        let (Some(major), Some(minor), Some(patch), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(err());
+        };
+        let pre = match pre {
+            None => vec![],
+            Some(pre) => pre
+                .split('.')
+                .map(|id| {
+                    if id.bytes().all(|b| b.is_ascii_digit()) {
+                        number(id).map(PreRelease::Numeric)
+                    } else if id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+                        Some(PreRelease::Alphanumeric(id.to_string()))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(err)?,
+        };
+        Ok(Version {
+            major: number(major).ok_or_else(err)?,
+            minor: number(minor).ok_or_else(err)?,
+            patch: number(patch).ok_or_else(err)?,
+            pre: PreReleases(pre),
+        })
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, id) in self.pre.0.iter().enumerate() {
+            let sep = if i == 0 { '-' } else { '.' };
+            match id {
+                PreRelease::Numeric(n) => write!(f, "{}{}", sep, n)?,
+                PreRelease::Alphanumeric(s) => write!(f, "{}{}", sep, s)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Target that compares strings by the semantic versions that they contain (see [`Version`]), snapping according to [`SnapPolicy`].
+/// Strings that aren't valid versions are [incomparable](crate::find::FindOrdering::ValIncomparable) with the target.
+#[derive(Clone, Debug)]
+pub struct VersionTarget {
+    pub version: Version,
+    pub snap: SnapPolicy,
+}
+
+impl VersionTarget {
+    /// Creates a target that doesn't snap.
+    pub fn new(version: Version) -> Self {
+        VersionTarget { version, snap: SnapPolicy::None }
+    }
+}
+
+impl<T: AsRef<str>, E> FindOrd<T, E> for VersionTarget {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        let ord = t.as_ref().parse::<Version>().ok().map(|v| Ord::cmp(&self.version, &v));
+        Ok(FindOrdering::from_target_ordering(ord, self.snap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+
+    #[test]
+    fn natural_order_compares_numbers_numerically() {
+        assert_eq!(natural_cmp("build-9", "build-10"), Ordering::Less);
+        assert_eq!(natural_cmp("build-10", "build-9"), Ordering::Greater);
+        assert_eq!(natural_cmp("a2b10", "a2b9"), Ordering::Greater);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("a1", "ab"), Ordering::Less);
+        assert_eq!(natural_cmp("x99999999999999999999999", "x100000000000000000000000"), Ordering::Less);
+        assert_eq!(natural_cmp("file-ä2", "file-ä10"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_order_breaks_ties_by_leading_zeros() {
+        assert_eq!(natural_cmp("a1", "a01"), Ordering::Less);
+        assert_eq!(natural_cmp("a01b", "a1c"), Ordering::Less);
+        assert_eq!(natural_cmp("a007", "a007"), Ordering::Equal);
+    }
+
+    #[test]
+    fn can_find_in_natural_order() {
+        let arr = ["build-1", "build-2", "build-9", "build-10", "build-11"];
+        assert_eq!(find_idx(&arr, &NaturalTarget::new("build-10")), Some(3));
+        assert_eq!(find_idx(&arr, &NaturalTarget::new("build-3")), None);
+        assert_eq!(find_idx(&arr, &NaturalTarget { value: "build-3", snap: SnapPolicy::Up }), Some(2));
+    }
+
+    fn version(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn can_parse_versions() {
+        assert_eq!(version("1.2.3"), Version { major: 1, minor: 2, patch: 3, pre: PreReleases::default() });
+        assert_eq!(version("v1.2.3+build.7").to_string(), "1.2.3");
+        assert_eq!(version("1.0.0-alpha.1.x-y").to_string(), "1.0.0-alpha.1.x-y");
+        for s in ["1.2", "1.2.3.4", "01.2.3", "1.2.3-", "1.2.3-a..b", "1.2.3-01", "1.2.x", ""] {
+            assert_eq!(s.parse::<Version>(), Err(ParseVersionError(s.to_string())), "{}", s);
+        }
+    }
+
+    #[test]
+    fn versions_are_ordered_by_precedence() {
+        // Example from the specification.
+        let ordered = [
+            "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11",
+            "1.0.0-rc.1", "1.0.0", "1.0.1", "1.2.0", "1.10.0", "2.0.0",
+        ];
+        for w in ordered.windows(2) {
+            assert!(version(w[0]) < version(w[1]), "{} < {}", w[0], w[1]);
+        }
+        assert_eq!(version("1.0.0+a"), version("1.0.0+b"));
+    }
+
+    #[test]
+    fn can_find_by_version() {
+        let arr = ["0.9.0", "1.0.0-rc.1", "1.0.0", "not-a-version", "1.2.0", "1.10.0"];
+        assert_eq!(find_idx(&arr, &VersionTarget::new(version("1.0.0"))), Some(2));
+        assert_eq!(find_idx(&arr, &VersionTarget::new(version("1.10.0"))), Some(5));
+        assert_eq!(find_idx(&arr, &VersionTarget::new(version("1.1.0"))), None);
+        assert_eq!(find_idx(&arr, &VersionTarget { version: version("1.1.0"), snap: SnapPolicy::Up }), Some(4));
+        assert_eq!(find_idx(&arr, &VersionTarget { version: version("1.0.0-rc.2"), snap: SnapPolicy::Down }), Some(1));
+    }
+}
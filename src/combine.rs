@@ -1,4 +1,132 @@
 use crate::find::{FindOrd, FindOrdering};
+use crate::op::Op;
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+
+/// Visitor of the targets in a [`FindOrdList`].
+pub trait FindOrdVisitor<T, E> {
+    /// Visits a single target.
+    /// Returns `false` to skip the remaining targets.
+    fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E>;
+}
+
+/// Ordered list of targets to be combined.
+///
+/// Besides vectors of boxed targets (which may be dynamically dispatched),
+/// the trait is implemented for arrays, slices, and tuples of targets.
+/// This allows combinations of concrete targets to be built without heap allocation or dynamic dispatch,
+/// such that the comparisons may be fully inlined.
+pub trait FindOrdList<T, E> {
+    /// Visits the targets in order until the visitor returns `false`.
+    /// Returns `false` if the visit was stopped by the visitor.
+    fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E>;
+
+    /// Number of targets in the list.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T, E, F: FindOrd<T, E> + ?Sized> FindOrdList<T, E> for Vec<Box<F>> {
+    fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E> {
+        for f in self.iter() {
+            if !v.visit(f.as_ref())? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, E, F: FindOrd<T, E>> FindOrdList<T, E> for [F] {
+    fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E> {
+        for f in self.iter() {
+            if !v.visit(f)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, E, F: FindOrd<T, E>, const N: usize> FindOrdList<T, E> for [F; N] {
+    fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E> {
+        self.as_slice().visit(v)
+    }
+
+    fn len(&self) -> usize {
+        N
+    }
+}
+
+/// Allows lists to be borrowed (e.g. as slices).
+impl<T, E, L: FindOrdList<T, E> + ?Sized> FindOrdList<T, E> for &L {
+    fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E> {
+        (**self).visit(v)
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+macro_rules! impl_find_ord_list_for_tuple {
+    ($n:expr; $($f:ident: $i:tt),+) => {
+        impl<T, E, $($f: FindOrd<T, E>),+> FindOrdList<T, E> for ($($f,)+) {
+            fn visit<V: FindOrdVisitor<T, E>>(&self, v: &mut V) -> Result<bool, E> {
+                $(
+                    if !v.visit(&self.$i)? {
+                        return Ok(false);
+                    }
+                )+
+                Ok(true)
+            }
+
+            fn len(&self) -> usize {
+                $n
+            }
+        }
+    };
+}
+
+impl_find_ord_list_for_tuple!(1; F0: 0);
+impl_find_ord_list_for_tuple!(2; F0: 0, F1: 1);
+impl_find_ord_list_for_tuple!(3; F0: 0, F1: 1, F2: 2);
+impl_find_ord_list_for_tuple!(4; F0: 0, F1: 1, F2: 2, F3: 3);
+impl_find_ord_list_for_tuple!(5; F0: 0, F1: 1, F2: 2, F3: 3, F4: 4);
+impl_find_ord_list_for_tuple!(6; F0: 0, F1: 1, F2: 2, F3: 3, F4: 4, F5: 5);
+impl_find_ord_list_for_tuple!(7; F0: 0, F1: 1, F2: 2, F3: 3, F4: 4, F5: 5, F6: 6);
+impl_find_ord_list_for_tuple!(8; F0: 0, F1: 1, F2: 2, F3: 3, F4: 4, F5: 5, F6: 6, F7: 7);
+
+/// Sum of the cost hints of the targets in a list.
+fn cost_hint_sum<T, E, L: FindOrdList<T, E> + ?Sized>(l: &L) -> u64 {
+    struct Visitor(u64);
+    impl<T, E> FindOrdVisitor<T, E> for Visitor {
+        fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+            self.0 += f.cost_hint();
+            Ok(true)
+        }
+    }
+    let mut v = Visitor(0);
+    // Visiting never fails as the visitor never returns an error.
+    let _ = l.visit::<Visitor>(&mut v);
+    v.0
+}
+
+/// Orders boxed targets by increasing [`FindOrd::cost_hint`], keeping the relative order of targets with the same cost.
+fn sort_by_cost<T, E, F: FindOrd<T, E> + ?Sized>(fs: &mut [Box<F>]) {
+    fs.sort_by_key(|f| f.cost_hint());
+}
 
 /// Implementation of [`FindOrd`] that allows a value to be effectively compared
 /// against the "lowermost" target in a given set of targets.
@@ -10,47 +138,104 @@ use crate::find::{FindOrd, FindOrdering};
 /// This corresponds to logical OR ("any") of the target conditions when used as lower limit ("from")
 /// and AND ("all") when used as the upper limit ("to").
 ///
-/// The `is_valid_res` fields of the individual comparison results are ignored
-/// because combining them correctly in all cases is non-trivial at best.
-/// Instead, the returned value if `is_valid_res` is
-/// - `snap_downwards` if the result is [`FindOrdering::ValBelowTarget`]
-/// - `snap_upwards` if the result is [`FindOrdering::ValAboveTarget`].
-pub struct FindOrdCombineLower<T, E> {
-    // TODO Use slice? Lifetime vs Box?
-    pub combined: Vec<Box<dyn FindOrd<T, E>>>,
-    pub snap_downwards: bool,
-    pub snap_upwards: bool,
+/// The value of `is_valid_res` of the combined result is derived from the results of the individual targets,
+/// such that each target keeps its own snapping behavior:
+/// - If the result is [`FindOrdering::ValBelowTarget`] (i.e. all targets are above the value),
+///   it's valid if *any* of the targets considers it valid.
+/// - If the result is [`FindOrdering::ValAboveTarget`],
+///   it's valid if *all* of the targets that are below the value consider it valid.
+///
+/// If the search ends up with valid results on both sides of the target, the one above is chosen.
+/// For targets that each snap in at most one direction, this makes the combined result the lowermost
+/// of the results of the individual targets (provided that all of these exist).
+///
+/// Setting `snap` makes all results in the direction(s) of the policy valid,
+/// regardless of the individual targets.
+///
+/// The targets are compared in order and the remaining ones are skipped once the result is settled,
+/// i.e. when the value is above a target which doesn't consider it valid (or `snap` snaps upwards).
+/// Use [`FindOrdCombineLower::sort_by_cost`] to ensure that the cheapest targets are compared first.
+///
+/// The targets may be given as any [`FindOrdList`], e.g. a vector of boxed targets or a tuple of concrete ones.
+pub struct FindOrdCombineLower<C> {
+    pub combined: C,
+    pub snap: SnapPolicy,
 }
 
-impl<T, E> FindOrd<T, E> for FindOrdCombineLower<T, E> {
+impl<T, E, C: FindOrdList<T, E>> FindOrd<T, E> for FindOrdCombineLower<C> {
     fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
-        let mut val_below_all_targets = true; // value is below if all targets say so
-        let mut val_above_any_target = false; // value is above if any target says so
-        for f in self.combined.iter() {
-            match f.cmp(t)? {
-                FindOrdering::ValBelowTarget { .. } => {}
-                FindOrdering::ValAboveTarget { .. } => {
-                    val_below_all_targets = false;
-                    val_above_any_target = true;
-                }
-                FindOrdering::ValMatchesTarget => {
-                    val_below_all_targets = false;
+        struct Visitor<'a, T> {
+            t: &'a T,
+            snap_upwards: bool,
+            val_below_all_targets: bool, // value is below if all targets say so
+            val_above_any_target: bool, // value is above if any target says so
+            below_valid: bool, // result below is valid if any target says so
+            above_valid: bool, // result above is valid if all targets that the value is above say so
+            incomparable: bool, // value is incomparable if any target says so (unless the result is settled by another one)
+        }
+        impl<T, E> FindOrdVisitor<T, E> for Visitor<'_, T> {
+            fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+                match f.cmp(self.t)? {
+                    FindOrdering::ValBelowTarget { is_valid_res } => {
+                        self.below_valid |= is_valid_res;
+                    }
+                    FindOrdering::ValAboveTarget { is_valid_res } => {
+                        self.val_below_all_targets = false;
+                        self.val_above_any_target = true;
+                        self.above_valid &= is_valid_res;
+                        if self.snap_upwards || !is_valid_res {
+                            // Result is settled: No other target can make the result not be above or change its validity.
+                            return Ok(false);
+                        }
+                    }
+                    FindOrdering::ValMatchesTarget => {
+                        self.val_below_all_targets = false;
+                    }
+                    FindOrdering::ValIncomparable => {
+                        self.incomparable = true;
+                    }
                 }
+                Ok(true)
             }
         }
+        let mut v = Visitor {
+            t,
+            snap_upwards: self.snap.snaps_upwards(),
+            val_below_all_targets: true,
+            val_above_any_target: false,
+            below_valid: false,
+            above_valid: true,
+            incomparable: false,
+        };
+        let settled = !self.combined.visit(&mut v)?;
         Ok(
-            if val_below_all_targets {
-                //println!("cmp {:?} = val below target (valid={})", t, self.snap_downwards);
-                FindOrdering::ValBelowTarget { is_valid_res: self.snap_downwards }
-            } else if val_above_any_target {
-                //println!("cmp {:?} = val above target (valid={})", t, self.snap_upwards);
-                FindOrdering::ValAboveTarget { is_valid_res: self.snap_upwards }
+            if !settled && v.incomparable {
+                FindOrdering::ValIncomparable
+            } else if v.val_below_all_targets {
+                FindOrdering::ValBelowTarget { is_valid_res: self.snap.snaps_downwards() || v.below_valid }
+            } else if v.val_above_any_target {
+                FindOrdering::ValAboveTarget { is_valid_res: self.snap.snaps_upwards() || v.above_valid }
             } else {
-                //println!("cmp {:?} = match", t);
                 FindOrdering::ValMatchesTarget
             }
         )
     }
+
+    fn prefer_snap_downwards(&self, _below: &T, _above: &T) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        cost_hint_sum(&self.combined)
+    }
+}
+
+impl<F: ?Sized> FindOrdCombineLower<Vec<Box<F>>> {
+    /// Orders the targets by increasing [`FindOrd::cost_hint`] such that the cheapest ones are compared first.
+    /// Targets with the same cost keep their relative order.
+    pub fn sort_by_cost<T, E>(&mut self) where F: FindOrd<T, E> {
+        sort_by_cost(&mut self.combined);
+    }
 }
 
 /// Implementation of [`FindOrd`] that allows a value to be effectively compared
@@ -63,53 +248,321 @@ impl<T, E> FindOrd<T, E> for FindOrdCombineLower<T, E> {
 /// This corresponds to logical AND ("all") of the target conditions when used as lower limit ("from")
 /// and OR ("any") when used as the upper limit ("to").
 ///
-/// The `is_valid_res` fields of the individual comparison results are ignored
-/// because combining them correctly in all cases is non-trivial at best.
-/// Instead, the returned value if `is_valid_res` is
-/// - `snap_downwards` if the result is [`FindOrdering::ValBelowTarget`]
-/// - `snap_upwards` if the result is [`FindOrdering::ValAboveTarget`].
-pub struct FindOrdCombineUpper<T, E> {
-    pub combined: Vec<Box<dyn FindOrd<T, E>>>,
-    pub snap_downwards: bool,
-    pub snap_upwards: bool,
+/// The value of `is_valid_res` of the combined result is derived from the results of the individual targets,
+/// such that each target keeps its own snapping behavior:
+/// - If the result is [`FindOrdering::ValBelowTarget`],
+///   it's valid if *all* of the targets that are above the value consider it valid.
+/// - If the result is [`FindOrdering::ValAboveTarget`] (i.e. all targets are below the value),
+///   it's valid if *any* of the targets considers it valid.
+///
+/// If the search ends up with valid results on both sides of the target, the one below is chosen.
+/// For targets that each snap in at most one direction, this makes the combined result the uppermost
+/// of the results of the individual targets (provided that all of these exist).
+///
+/// Setting `snap` makes all results in the direction(s) of the policy valid,
+/// regardless of the individual targets.
+///
+/// The targets are compared in order and the remaining ones are skipped once the result is settled,
+/// i.e. when the value is below a target which doesn't consider it valid (or `snap` snaps downwards).
+/// Use [`FindOrdCombineUpper::sort_by_cost`] to ensure that the cheapest targets are compared first.
+///
+/// The targets may be given as any [`FindOrdList`], e.g. a vector of boxed targets or a tuple of concrete ones.
+pub struct FindOrdCombineUpper<C> {
+    pub combined: C,
+    pub snap: SnapPolicy,
 }
 
-impl<T, E> FindOrd<T, E> for FindOrdCombineUpper<T, E> {
+impl<T, E, C: FindOrdList<T, E>> FindOrd<T, E> for FindOrdCombineUpper<C> {
     fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
-        let mut val_below_any_target = false; // value is below if any target says so
-        let mut val_above_all_targets = true; // value is above if all targets say so
-        for f in self.combined.iter() {
-            match f.cmp(t)? {
-                FindOrdering::ValBelowTarget { .. } => {
-                    val_below_any_target = true;
-                    val_above_all_targets = false;
+        struct Visitor<'a, T> {
+            t: &'a T,
+            snap_downwards: bool,
+            val_below_any_target: bool, // value is below if any target says so
+            val_above_all_targets: bool, // value is above if all targets say so
+            below_valid: bool, // result below is valid if all targets that the value is below say so
+            above_valid: bool, // result above is valid if any target says so
+            incomparable: bool, // value is incomparable if any target says so (unless the result is settled by another one)
+        }
+        impl<T, E> FindOrdVisitor<T, E> for Visitor<'_, T> {
+            fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+                match f.cmp(self.t)? {
+                    FindOrdering::ValBelowTarget { is_valid_res } => {
+                        self.val_below_any_target = true;
+                        self.val_above_all_targets = false;
+                        self.below_valid &= is_valid_res;
+                        if self.snap_downwards || !is_valid_res {
+                            // Result is settled: No other target can make the result not be below or change its validity.
+                            return Ok(false);
+                        }
+                    }
+                    FindOrdering::ValAboveTarget { is_valid_res } => {
+                        self.above_valid |= is_valid_res;
+                    }
+                    FindOrdering::ValMatchesTarget => {
+                        self.val_above_all_targets = false;
+                    }
+                    FindOrdering::ValIncomparable => {
+                        self.incomparable = true;
+                    }
                 }
-                FindOrdering::ValAboveTarget { .. } => {}
-                FindOrdering::ValMatchesTarget => {
-                    val_above_all_targets = false;
+                Ok(true)
+            }
+        }
+        let mut v = Visitor {
+            t,
+            snap_downwards: self.snap.snaps_downwards(),
+            val_below_any_target: false,
+            val_above_all_targets: true,
+            below_valid: true,
+            above_valid: false,
+            incomparable: false,
+        };
+        let settled = !self.combined.visit(&mut v)?;
+        Ok(
+            if !settled && v.incomparable {
+                FindOrdering::ValIncomparable
+            } else if v.val_above_all_targets {
+                FindOrdering::ValAboveTarget { is_valid_res: self.snap.snaps_upwards() || v.above_valid }
+            } else if v.val_below_any_target {
+                FindOrdering::ValBelowTarget { is_valid_res: self.snap.snaps_downwards() || v.below_valid }
+            } else {
+                FindOrdering::ValMatchesTarget
+            }
+        )
+    }
+
+    fn prefer_snap_downwards(&self, _below: &T, _above: &T) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        cost_hint_sum(&self.combined)
+    }
+}
+
+impl<F: ?Sized> FindOrdCombineUpper<Vec<Box<F>>> {
+    /// Orders the targets by increasing [`FindOrd::cost_hint`] such that the cheapest ones are compared first.
+    /// Targets with the same cost keep their relative order.
+    pub fn sort_by_cost<T, E>(&mut self) where F: FindOrd<T, E> {
+        sort_by_cost(&mut self.combined);
+    }
+}
+
+/// Implementation of [`FindOrd`] that allows a value to be effectively compared
+/// against the `threshold`'th lowermost target in a given set of targets.
+///
+/// That is, the comparison result is defined as "greater" if the value is above at least `threshold` targets
+/// and "lesser" if it's below so many targets that this can't be the case.
+/// This generalizes [FindOrdCombineLower] (`threshold` 1) and [FindOrdCombineUpper] (`threshold` equal to the number of targets).
+/// If `threshold` is 0, all values are above the combined target and if it exceeds the number of targets, all values are below.
+///
+/// This corresponds to "at least `threshold`" of the target conditions when used as lower limit ("from").
+/// When used as upper limit ("to"), it corresponds to "at least *n*-`threshold`+1" of the *n* conditions.
+///
+/// The value of `is_valid_res` of the combined result is derived from the results of the individual targets,
+/// such that each target keeps its own snapping behavior:
+/// - If the result is [`FindOrdering::ValBelowTarget`] and the value is below *b* of the *n* targets,
+///   it's valid if at most *n*-`threshold` of these consider it invalid.
+/// - If the result is [`FindOrdering::ValAboveTarget`],
+///   it's valid if less than `threshold` of the targets that are below the value consider it invalid.
+///
+/// If the search ends up with valid results on both sides of the target,
+/// the chosen one is the one that at least `threshold` of the individual targets have their own results at or below.
+/// For targets that each snap in at most one direction, this makes the combined result the `threshold`'th lowermost
+/// of the results of the individual targets (provided that all of these exist).
+///
+/// Setting `snap` makes all results in the direction(s) of the policy valid,
+/// regardless of the individual targets.
+///
+/// The targets are compared in order and the remaining ones are skipped once the result is settled.
+/// Use [`FindOrdCombineThreshold::sort_by_cost`] to ensure that the cheapest targets are compared first.
+///
+/// The targets may be given as any [`FindOrdList`], e.g. a vector of boxed targets or a tuple of concrete ones.
+pub struct FindOrdCombineThreshold<C> {
+    pub combined: C,
+    pub threshold: usize,
+    pub snap: SnapPolicy,
+}
+
+impl<T, E, C: FindOrdList<T, E>> FindOrd<T, E> for FindOrdCombineThreshold<C> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        struct Visitor<'a, T> {
+            t: &'a T,
+            n: usize,
+            k: usize,
+            snap_downwards: bool,
+            snap_upwards: bool,
+            above: usize, // number of targets that the value is above
+            above_invalid: usize, // number of targets that the value is above and which don't consider it valid
+            below: usize, // number of targets that the value is below
+            below_invalid: usize, // number of targets that the value is below and which don't consider it valid
+            incomparable: bool, // value is incomparable if any target says so (unless the result is settled by another one)
+        }
+        impl<T, E> FindOrdVisitor<T, E> for Visitor<'_, T> {
+            fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+                match f.cmp(self.t)? {
+                    FindOrdering::ValBelowTarget { is_valid_res } => {
+                        self.below += 1;
+                        if !is_valid_res {
+                            self.below_invalid += 1;
+                        }
+                        if self.below + self.k > self.n && (self.snap_downwards || self.below_invalid + self.k > self.n) {
+                            // Result is settled: Value is below and no other target can change its validity.
+                            return Ok(false);
+                        }
+                    }
+                    FindOrdering::ValAboveTarget { is_valid_res } => {
+                        self.above += 1;
+                        if !is_valid_res {
+                            self.above_invalid += 1;
+                        }
+                        if self.above >= self.k && (self.snap_upwards || self.above_invalid >= self.k) {
+                            // Result is settled: Value is above and no other target can change its validity.
+                            return Ok(false);
+                        }
+                    }
+                    FindOrdering::ValMatchesTarget => {}
+                    FindOrdering::ValIncomparable => {
+                        self.incomparable = true;
+                    }
                 }
+                Ok(true)
             }
         }
+        let n = self.combined.len();
+        let k = self.threshold;
+        let mut v = Visitor {
+            t,
+            n,
+            k,
+            snap_downwards: self.snap.snaps_downwards(),
+            snap_upwards: self.snap.snaps_upwards(),
+            above: 0,
+            above_invalid: 0,
+            below: 0,
+            below_invalid: 0,
+            incomparable: false,
+        };
+        let settled = !self.combined.visit(&mut v)?;
         Ok(
-            if val_above_all_targets {
-                //println!("cmp {:?} = val above target (valid={})", t, self.snap_upwards);
-                FindOrdering::ValAboveTarget { is_valid_res: self.snap_upwards }
-            } else if val_below_any_target {
-                //println!("cmp {:?} = val below target (valid={})", t, self.snap_downwards);
-                FindOrdering::ValBelowTarget { is_valid_res: self.snap_downwards }
+            if !settled && v.incomparable {
+                FindOrdering::ValIncomparable
+            } else if v.above >= k {
+                FindOrdering::ValAboveTarget { is_valid_res: self.snap.snaps_upwards() || v.above_invalid < k }
+            } else if v.below + k > n {
+                FindOrdering::ValBelowTarget { is_valid_res: self.snap.snaps_downwards() || v.below_invalid + k <= n }
             } else {
-                //println!("cmp {:?} = match", t);
                 FindOrdering::ValMatchesTarget
             }
         )
     }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        // Count the targets whose own result is at or below 'below':
+        // That is the case if 'below' is above (or matches) the target
+        // or if it's a valid result below the target and 'above' is above the target (i.e. it's snapped down to).
+        struct Visitor<'a, T> {
+            below: &'a T,
+            above: &'a T,
+            count: usize,
+        }
+        impl<T, E> FindOrdVisitor<T, E> for Visitor<'_, T> {
+            fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+                let res_at_or_below = match f.cmp(self.below)? {
+                    FindOrdering::ValAboveTarget { .. } | FindOrdering::ValMatchesTarget => true,
+                    FindOrdering::ValBelowTarget { is_valid_res: true } => matches!(f.cmp(self.above)?, FindOrdering::ValAboveTarget { .. }),
+                    FindOrdering::ValBelowTarget { is_valid_res: false } | FindOrdering::ValIncomparable => false,
+                };
+                if res_at_or_below {
+                    self.count += 1;
+                }
+                Ok(true)
+            }
+        }
+        let mut v = Visitor { below, above, count: 0 };
+        self.combined.visit(&mut v)?;
+        Ok(v.count >= self.threshold)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        cost_hint_sum(&self.combined)
+    }
+}
+
+impl<F: ?Sized> FindOrdCombineThreshold<Vec<Box<F>>> {
+    /// Orders the targets by increasing [`FindOrd::cost_hint`] such that the cheapest ones are compared first.
+    /// Targets with the same cost keep their relative order.
+    pub fn sort_by_cost<T, E>(&mut self) where F: FindOrd<T, E> {
+        sort_by_cost(&mut self.combined);
+    }
+}
+
+/// Implementation of [`FindOrd`] that compares values lexicographically against a sequence of targets,
+/// e.g. one per field of a composite key like `(tenant, timestamp, seq)`.
+///
+/// The value is compared against the targets in order until one of them doesn't match;
+/// that target determines whether the value is below or above the combined target
+/// (the snapping of the individual targets is ignored).
+/// If the value matches all targets, it's equal to the combined target.
+/// The combined ordering is then interpreted according to `op`, with `snap` applying to [`Op::Eq`].
+///
+/// The targets may form a prefix of the key (e.g. only the tenant).
+/// Many values may then be equal to the combined target, and [`Op::Ge`] and [`Op::Le`] find the first and last of them.
+/// Using these as the lower and upper target of [`crate::find_range::find_range`] thus finds the full range
+/// of values with the given prefix.
+///
+/// The targets may be given as any [`FindOrdList`], e.g. a tuple of concrete ones,
+/// or a reference to one such that both bounds of a range may share the same targets.
+pub struct FindOrdCombineLexicographic<C> {
+    pub combined: C,
+    pub op: Op,
+    pub snap: SnapPolicy,
+}
+
+impl<C> FindOrdCombineLexicographic<C> {
+    /// Creates a combined target that doesn't snap when used with [`Op::Eq`].
+    pub fn new(combined: C, op: Op) -> Self {
+        FindOrdCombineLexicographic { combined, op, snap: SnapPolicy::None }
+    }
+}
+
+impl<T, E, C: FindOrdList<T, E>> FindOrd<T, E> for FindOrdCombineLexicographic<C> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        struct Visitor<'a, T> {
+            t: &'a T,
+            ord: Option<Ordering>, // ordering of the combined target relative to the value
+        }
+        impl<T, E> FindOrdVisitor<T, E> for Visitor<'_, T> {
+            fn visit<F: FindOrd<T, E> + ?Sized>(&mut self, f: &F) -> Result<bool, E> {
+                self.ord = match f.cmp(self.t)? {
+                    FindOrdering::ValMatchesTarget => return Ok(true),
+                    FindOrdering::ValBelowTarget { .. } => Some(Ordering::Greater),
+                    FindOrdering::ValAboveTarget { .. } => Some(Ordering::Less),
+                    FindOrdering::ValIncomparable => None,
+                };
+                Ok(false)
+            }
+        }
+        let mut v = Visitor { t, ord: Some(Ordering::Equal) };
+        self.combined.visit(&mut v)?;
+        Ok(self.op.find_ordering(v.ord, self.snap))
+    }
+
+    fn cost_hint(&self) -> u64 {
+        cost_hint_sum(&self.combined)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ext::Within;
+    use crate::key::KeyTarget;
+    use crate::snap::{with_snap, SnapPolicy};
     use crate::test_util::helpers::*;
     use assert_matches::assert_matches; // use stdlib version once it's stable (https://github.com/rust-lang/rust/issues/82775)
+    use std::cell::Cell;
+    use std::rc::Rc;
 
     #[derive(Clone, Debug, PartialEq)]
     struct Data {
@@ -145,6 +598,11 @@ mod tests {
         }
     }
 
+    /// Target for a lower bound on a field of [`Data`] that snaps upwards.
+    fn at_least(key: fn(&Data) -> f64, val: f64) -> KeyTarget<f64, fn(&Data) -> f64> {
+        KeyTarget { value: val, key, snap: SnapPolicy::Up }
+    }
+
     // TODO: Test much more thoroughly.
 
     #[test]
@@ -154,8 +612,7 @@ mod tests {
                 Box::new(Target { field: Field::A, val: 1.0 }), // a >= 1.0
                 Box::new(Target { field: Field::B, val: 2.0 }), // b >= 2.0
             ],
-            snap_downwards: false, // ignored
-            snap_upwards: true,    // ignored
+            snap: SnapPolicy::Up,
         };
 
         let and2 = FindOrdCombineUpper {
@@ -163,14 +620,12 @@ mod tests {
                 Box::new(Target { field: Field::A, val: 0.1 }), // a >= 0.1
                 Box::new(Target { field: Field::B, val: 4.0 }), // b >= 4.1
             ],
-            snap_downwards: false, // ignored
-            snap_upwards: true,    // ignored
+            snap: SnapPolicy::Up,
         };
 
         let and1_or_and2 = FindOrdCombineLower {
             combined: vec![Box::new(and1), Box::new(and2)],
-            snap_downwards: false,
-            snap_upwards: true,
+            snap: SnapPolicy::Up,
         };
 
         let data = [
@@ -198,8 +653,7 @@ mod tests {
                 Box::new(Target { field: Field::A, val: 1.0 }), // a >= 1.0
                 Box::new(Target { field: Field::B, val: 2.0 }), // b >= 2.0
             ],
-            snap_downwards: false, // ignored
-            snap_upwards: true,    // ignored
+            snap: SnapPolicy::Up,
         };
 
         let or2 = FindOrdCombineLower {
@@ -207,14 +661,94 @@ mod tests {
                 Box::new(Target { field: Field::A, val: 0.1 }), // a >= 0.1
                 Box::new(Target { field: Field::B, val: 4.0 }), // b >= 4.1
             ],
-            snap_downwards: false, // ignored
-            snap_upwards: true,    // ignored
+            snap: SnapPolicy::Up,
+        };
+
+        let or1_and_or2 = FindOrdCombineUpper {
+            combined: vec![Box::new(or1), Box::new(or2)],
+            snap: SnapPolicy::Up,
+        };
+
+        let data = [
+            Data { a: 0.0, b: 1.0 },
+            Data { a: 1.0, b: 2.0 },
+            Data { a: 2.0, b: 4.0 },
+        ];
+        let r = crate::find::find(
+            &new_lookup(&data),
+            &or1_and_or2,
+            0,
+            data.len() as i64 - 1,
+        ).unwrap();
+
+        assert_matches!(
+            r.element,
+            Some(crate::find::Element {val: d, idx: 1}) if d == Data {a: 1.0 , b: 2.0 }
+        );
+    }
+
+    #[test]
+    fn can_find_element_using_key_targets_combined_into_or_of_and() {
+        let and1 = FindOrdCombineUpper {
+            combined: vec![
+                Box::new(at_least(|d| d.a, 1.0)), // a >= 1.0
+                Box::new(at_least(|d| d.b, 2.0)), // b >= 2.0
+            ],
+            snap: SnapPolicy::Up,
+        };
+
+        let and2 = FindOrdCombineUpper {
+            combined: vec![
+                Box::new(at_least(|d| d.a, 0.1)), // a >= 0.1
+                Box::new(at_least(|d| d.b, 4.0)), // b >= 4.1
+            ],
+            snap: SnapPolicy::Up,
+        };
+
+        let and1_or_and2 = FindOrdCombineLower {
+            combined: vec![Box::new(and1), Box::new(and2)],
+            snap: SnapPolicy::Up,
+        };
+
+        let data = [
+            Data { a: 0.0, b: 1.0 },
+            Data { a: 1.0, b: 2.0 },
+            Data { a: 2.0, b: 4.0 },
+        ];
+        let r = crate::find::find(
+            &new_lookup(&data),
+            &and1_or_and2,
+            0,
+            data.len() as i64 - 1,
+        ).unwrap();
+
+        assert_matches!(
+            r.element,
+            Some(crate::find::Element {val: d, idx: 1}) if d == Data {a: 1.0 , b: 2.0 }
+        );
+    }
+
+    #[test]
+    fn can_find_element_using_key_targets_combined_using_and_of_or() {
+        let or1 = FindOrdCombineLower {
+            combined: vec![
+                Box::new(at_least(|d| d.a, 1.0)), // a >= 1.0
+                Box::new(at_least(|d| d.b, 2.0)), // b >= 2.0
+            ],
+            snap: SnapPolicy::Up,
+        };
+
+        let or2 = FindOrdCombineLower {
+            combined: vec![
+                Box::new(at_least(|d| d.a, 0.1)), // a >= 0.1
+                Box::new(at_least(|d| d.b, 4.0)), // b >= 4.1
+            ],
+            snap: SnapPolicy::Up,
         };
 
         let or1_and_or2 = FindOrdCombineUpper {
             combined: vec![Box::new(or1), Box::new(or2)],
-            snap_downwards: false,
-            snap_upwards: true,
+            snap: SnapPolicy::Up,
         };
 
         let data = [
@@ -234,4 +768,341 @@ mod tests {
             Some(crate::find::Element {val: d, idx: 1}) if d == Data {a: 1.0 , b: 2.0 }
         );
     }
+
+    fn find_snapped(arr: &[i64], target: &impl FindOrd<i64, String>) -> Option<i64> {
+        crate::find::find(&new_lookup(arr), target, 0, arr.len() as i64 - 1)
+            .unwrap()
+            .element
+            .map(|e| e.val)
+    }
+
+    #[test]
+    fn combining_mixed_snapping_targets_finds_lowermost_or_uppermost_result() {
+        let arr = [0, 2, 4, 6, 8];
+        for u in -1..=9 {
+            for d in -1..=9 {
+                let (Some(ur), Some(dr)) = (
+                    find_snapped(&arr, &with_snap(u, SnapPolicy::Up)),
+                    find_snapped(&arr, &with_snap(d, SnapPolicy::Down)),
+                ) else {
+                    continue;
+                };
+                let lower = FindOrdCombineLower {
+                    combined: vec![Box::new(with_snap(u, SnapPolicy::Up)), Box::new(with_snap(d, SnapPolicy::Down))],
+                    snap: SnapPolicy::None,
+                };
+                let lower_rev = FindOrdCombineLower {
+                    combined: vec![Box::new(with_snap(d, SnapPolicy::Down)), Box::new(with_snap(u, SnapPolicy::Up))],
+                    snap: SnapPolicy::None,
+                };
+                let upper = FindOrdCombineUpper {
+                    combined: vec![Box::new(with_snap(u, SnapPolicy::Up)), Box::new(with_snap(d, SnapPolicy::Down))],
+                    snap: SnapPolicy::None,
+                };
+                let upper_rev = FindOrdCombineUpper {
+                    combined: vec![Box::new(with_snap(d, SnapPolicy::Down)), Box::new(with_snap(u, SnapPolicy::Up))],
+                    snap: SnapPolicy::None,
+                };
+                assert_eq!(find_snapped(&arr, &lower), Some(ur.min(dr)), "lower: u={} d={}", u, d);
+                assert_eq!(find_snapped(&arr, &lower_rev), Some(ur.min(dr)), "lower: u={} d={}", u, d);
+                assert_eq!(find_snapped(&arr, &upper), Some(ur.max(dr)), "upper: u={} d={}", u, d);
+                assert_eq!(find_snapped(&arr, &upper_rev), Some(ur.max(dr)), "upper: u={} d={}", u, d);
+            }
+        }
+    }
+
+    #[test]
+    fn combining_targets_with_same_snapping_keeps_snapping() {
+        let arr = [0, 2, 4, 6, 8];
+        let lower = FindOrdCombineLower {
+            combined: vec![Box::new(with_snap(3, SnapPolicy::Down)), Box::new(with_snap(5, SnapPolicy::Down))],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &lower), Some(2));
+        let upper = FindOrdCombineUpper {
+            combined: vec![Box::new(with_snap(3, SnapPolicy::Up)), Box::new(with_snap(5, SnapPolicy::Up))],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &upper), Some(6));
+    }
+
+    #[test]
+    fn combining_non_snapping_targets_does_not_snap() {
+        let arr = [0, 2, 4, 6, 8];
+        let lower = FindOrdCombineLower {
+            combined: vec![Box::new(3), Box::new(5)],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &lower), None);
+        let upper = FindOrdCombineUpper {
+            combined: vec![Box::new(3), Box::new(5)],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &upper), None);
+    }
+
+    #[test]
+    fn combining_targets_keeps_their_snapping_tolerance() {
+        let arr = [0, 2, 4, 6, 8];
+        let within = |v: i64, snap, tolerance: u64| Within { target: with_snap(v, snap), distance: move |t: &i64| t.abs_diff(v), tolerance };
+        let upper = FindOrdCombineUpper {
+            combined: (within(3, SnapPolicy::Up, 1), within(5, SnapPolicy::Up, 1)),
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &upper), Some(6));
+        let upper = FindOrdCombineUpper {
+            combined: (within(3, SnapPolicy::Up, 1), within(5, SnapPolicy::Up, 0)),
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &upper), None);
+        let lower = FindOrdCombineLower {
+            combined: (within(3, SnapPolicy::Down, 1), within(7, SnapPolicy::Down, 0)),
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &lower), Some(2));
+        let lower = FindOrdCombineLower {
+            combined: (within(3, SnapPolicy::Down, 0), within(7, SnapPolicy::Down, 1)),
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &lower), None);
+    }
+
+    #[test]
+    fn combination_skips_values_incomparable_with_any_target_unless_settled() {
+        let data = [
+            Data { a: 0.0, b: 0.0 },
+            Data { a: 1.0, b: f64::NAN },
+            Data { a: 2.0, b: 2.0 },
+            Data { a: 3.0, b: 3.0 },
+        ];
+        let lower = FindOrdCombineLower {
+            combined: vec![Box::new(at_least(|d| d.a, 2.5)), Box::new(at_least(|d| d.b, 1.5))],
+            snap: SnapPolicy::None,
+        };
+        assert_matches!(FindOrd::<Data, String>::cmp(&lower, &data[1]), Ok(FindOrdering::ValIncomparable));
+        assert_eq!(find_idx(&data, &lower), Some(2));
+        // Being above a target that doesn't consider the value valid settles the result regardless of the NaN.
+        let lower = FindOrdCombineLower {
+            combined: (KeyTarget::new(0.5, |d: &Data| d.a), KeyTarget { value: 1.5, key: |d: &Data| d.b, snap: SnapPolicy::Up }),
+            snap: SnapPolicy::None,
+        };
+        assert_matches!(FindOrd::<Data, String>::cmp(&lower, &data[1]), Ok(FindOrdering::ValAboveTarget { is_valid_res: false }));
+    }
+
+    #[test]
+    fn combining_tuples_arrays_and_slices_matches_boxed_targets() {
+        let arr = [0, 2, 4, 6, 8];
+        let boxed: FindOrdCombineLower<Vec<Box<dyn FindOrd<i64, String>>>> = FindOrdCombineLower {
+            combined: vec![Box::new(with_snap(3, SnapPolicy::Up)), Box::new(5)],
+            snap: SnapPolicy::None,
+        };
+        let tuple = FindOrdCombineLower {
+            combined: (with_snap(3, SnapPolicy::Up), 5),
+            snap: SnapPolicy::None,
+        };
+        let targets = [with_snap(3, SnapPolicy::Up), with_snap(5, SnapPolicy::Down)];
+        let array = FindOrdCombineLower {
+            combined: [with_snap(3, SnapPolicy::Up), with_snap(5, SnapPolicy::Down)],
+            snap: SnapPolicy::None,
+        };
+        let slice = FindOrdCombineLower {
+            combined: &targets[..],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_snapped(&arr, &tuple), find_snapped(&arr, &boxed));
+        assert_eq!(find_snapped(&arr, &array), Some(4));
+        assert_eq!(find_snapped(&arr, &slice), Some(4));
+        assert_eq!(FindOrd::<i64, String>::cost_hint(&tuple), 2);
+    }
+
+    /// Target that counts the number of times that it's compared.
+    struct Counting {
+        val: i64,
+        cost: u64,
+        count: Rc<Cell<usize>>,
+    }
+
+    impl FindOrd<i64, String> for Counting {
+        fn cmp(&self, t: &i64) -> Result<FindOrdering, String> {
+            self.count.set(self.count.get() + 1);
+            FindOrd::<i64, String>::cmp(&self.val, t)
+        }
+
+        fn cost_hint(&self) -> u64 {
+            self.cost
+        }
+    }
+
+    fn counting(val: i64, cost: u64) -> (Counting, Rc<Cell<usize>>) {
+        let count = Rc::new(Cell::new(0));
+        (Counting { val, cost, count: count.clone() }, count)
+    }
+
+    #[test]
+    fn lower_combination_skips_remaining_targets_once_result_is_settled() {
+        let (c, count) = counting(5, 1);
+        let lower = FindOrdCombineLower {
+            combined: (3, c),
+            snap: SnapPolicy::None,
+        };
+        assert_matches!(lower.cmp(&4), Ok(FindOrdering::ValAboveTarget { is_valid_res: false }));
+        assert_eq!(count.get(), 0);
+        // Result isn't settled before all targets are compared.
+        assert_matches!(lower.cmp(&2), Ok(FindOrdering::ValBelowTarget { is_valid_res: false }));
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn upper_combination_skips_remaining_targets_once_result_is_settled() {
+        let (c, count) = counting(3, 1);
+        let upper = FindOrdCombineUpper {
+            combined: (5, c),
+            snap: SnapPolicy::None,
+        };
+        assert_matches!(upper.cmp(&4), Ok(FindOrdering::ValBelowTarget { is_valid_res: false }));
+        assert_eq!(count.get(), 0);
+        // Result isn't settled before all targets are compared.
+        assert_matches!(upper.cmp(&6), Ok(FindOrdering::ValAboveTarget { is_valid_res: false }));
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn combination_does_not_skip_targets_that_may_invalidate_result() {
+        let (c, count) = counting(5, 1);
+        let lower = FindOrdCombineLower {
+            combined: (with_snap(3, SnapPolicy::Up), c),
+            snap: SnapPolicy::None,
+        };
+        assert_matches!(lower.cmp(&6), Ok(FindOrdering::ValAboveTarget { is_valid_res: false }));
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn sorting_by_cost_compares_cheapest_targets_first() {
+        let (expensive, expensive_count) = counting(5, 10);
+        let (cheap, cheap_count) = counting(3, 1);
+        let mut lower = FindOrdCombineLower {
+            combined: vec![Box::new(expensive) as Box<dyn FindOrd<i64, String>>, Box::new(cheap)],
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(lower.cost_hint(), 11);
+        lower.sort_by_cost();
+        assert_matches!(lower.cmp(&4), Ok(FindOrdering::ValAboveTarget { is_valid_res: false }));
+        assert_eq!(cheap_count.get(), 1);
+        assert_eq!(expensive_count.get(), 0);
+    }
+
+    #[test]
+    fn threshold_of_mixed_snapping_targets_finds_threshold_lowermost_result() {
+        let arr = [0, 2, 4, 6, 8];
+        let snap = |up| if up { SnapPolicy::Up } else { SnapPolicy::Down };
+        let ts = -1..=9;
+        for v1 in ts.clone() {
+            for v2 in ts.clone() {
+                for v3 in ts.clone() {
+                    for snaps in 0..8 {
+                        // Bits of 'snaps' determine the snapping direction of each target.
+                        let targets = [(v1, snap(snaps & 1 != 0)), (v2, snap(snaps & 2 != 0)), (v3, snap(snaps & 4 != 0))];
+                        let Some(mut rs) = targets
+                            .iter()
+                            .map(|&(v, s)| find_snapped(&arr, &with_snap(v, s)))
+                            .collect::<Option<Vec<_>>>() else {
+                            continue;
+                        };
+                        rs.sort();
+                        for threshold in 1..=3 {
+                            let combined = FindOrdCombineThreshold {
+                                combined: targets.map(|(v, s)| with_snap(v, s)),
+                                threshold,
+                                snap: SnapPolicy::None,
+                            };
+                            assert_eq!(
+                                find_snapped(&arr, &combined),
+                                Some(rs[threshold - 1]),
+                                "threshold {} of {:?}", threshold, targets,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threshold_of_watermarks() {
+        // Find the first record where at least 2 of 3 replicas are past their watermark.
+        #[derive(Clone, Debug, PartialEq)]
+        struct Replicas([i64; 3]);
+        struct Watermark {
+            replica: usize,
+            val: i64,
+        }
+        impl FindOrd<Replicas, String> for Watermark {
+            fn cmp(&self, t: &Replicas) -> Result<FindOrdering, String> {
+                FindOrd::<i64, String>::cmp(&with_snap(self.val, SnapPolicy::Up), &t.0[self.replica])
+            }
+        }
+        let data = [
+            Replicas([1, 0, 0]),
+            Replicas([5, 2, 1]),
+            Replicas([6, 4, 3]),
+            Replicas([9, 5, 7]),
+        ];
+        let threshold = |k| FindOrdCombineThreshold {
+            combined: [0, 1, 2].map(|replica| Watermark { replica, val: 5 }),
+            threshold: k,
+            snap: SnapPolicy::None,
+        };
+        assert_eq!(find_idx(&data, &threshold(1)), Some(1));
+        assert_eq!(find_idx(&data, &threshold(2)), Some(3));
+        assert_eq!(find_idx(&data, &threshold(3)), Some(3));
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Key {
+        tenant: &'static str,
+        ts: i64,
+        seq: i64,
+    }
+
+    fn keys() -> Vec<Key> {
+        [("a", 1, 0), ("b", 1, 0), ("b", 2, 0), ("b", 2, 1), ("b", 3, 0), ("c", 1, 0)]
+            .into_iter()
+            .map(|(tenant, ts, seq)| Key { tenant, ts, seq })
+            .collect()
+    }
+
+    #[test]
+    fn lexicographic_combination_compares_targets_in_order() {
+        let data = keys();
+        let full = |tenant, ts, seq, op| FindOrdCombineLexicographic::new(
+            (KeyTarget::new(tenant, |k: &Key| k.tenant), KeyTarget::new(ts, |k: &Key| k.ts), KeyTarget::new(seq, |k: &Key| k.seq)),
+            op,
+        );
+        assert_eq!(find_idx(&data, &full("b", 2, 1, Op::Eq)), Some(3));
+        assert_eq!(find_idx(&data, &full("b", 2, 2, Op::Eq)), None);
+        assert_eq!(find_idx(&data, &full("b", 2, 2, Op::Ge)), Some(4));
+        assert_eq!(find_idx(&data, &full("b", 2, 2, Op::Lt)), Some(3));
+        assert_eq!(find_idx(&data, &full("a", 5, 0, Op::Gt)), Some(1));
+    }
+
+    #[test]
+    fn lexicographic_prefix_finds_full_sub_range() {
+        let data = keys();
+        fn find_prefix_range(data: &[Key], prefix: &impl FindOrdList<Key, String>) -> (Option<i64>, Option<i64>) {
+            let (l, u) = crate::find_range::find_range(
+                &new_lookup(data),
+                &FindOrdCombineLexicographic::new(prefix, Op::Ge),
+                &FindOrdCombineLexicographic::new(prefix, Op::Le),
+                0,
+                data.len() as i64 - 1,
+            ).unwrap();
+            (l.map(|e| e.idx), u.map(|e| e.idx))
+        }
+        assert_eq!(find_prefix_range(&data, &(KeyTarget::new("b", |k: &Key| k.tenant),)), (Some(1), Some(4)));
+        assert_eq!(find_prefix_range(&data, &(KeyTarget::new("a", |k: &Key| k.tenant),)), (Some(0), Some(0)));
+        assert_eq!(find_prefix_range(&data, &(KeyTarget::new("b", |k: &Key| k.tenant), KeyTarget::new(2, |k: &Key| k.ts))), (Some(2), Some(3)));
+        assert_eq!(find_prefix_range(&data, &(KeyTarget::new("bb", |k: &Key| k.tenant),)), (None, None));
+        assert_eq!(find_prefix_range(&data, &(KeyTarget::new("d", |k: &Key| k.tenant),)), (None, None));
+    }
 }
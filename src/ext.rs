@@ -0,0 +1,385 @@
+use crate::combine::{FindOrdCombineLower, FindOrdCombineUpper};
+use crate::find::{FindOrd, FindOrdering, IncomparableError, IncomparablePolicy};
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// Extension methods for building compound targets from expressions like `a.and(b).snap_up()`.
+///
+/// The methods are available on all implementations of [`FindOrd`].
+pub trait FindOrdExt<T, E>: FindOrd<T, E> {
+    /// Combines the target with `other` such that the result is the uppermost of the results of the two targets.
+    ///
+    /// If both targets are lower bounds (i.e. a value satisfies the target if it's at or above it),
+    /// this finds the first value satisfying both.
+    /// For upper bounds, the conjunction is expressed using [`FindOrdExt::or`].
+    fn and<O: FindOrd<T, E>>(self, other: O) -> FindOrdCombineUpper<(Self, O)>
+    where
+        Self: Sized,
+    {
+        FindOrdCombineUpper { combined: (self, other), snap: SnapPolicy::None }
+    }
+
+    /// Combines the target with `other` such that the result is the lowermost of the results of the two targets.
+    ///
+    /// If both targets are lower bounds (i.e. a value satisfies the target if it's at or above it),
+    /// this finds the first value satisfying either.
+    /// For upper bounds, the disjunction is expressed using [`FindOrdExt::and`].
+    fn or<O: FindOrd<T, E>>(self, other: O) -> FindOrdCombineLower<(Self, O)>
+    where
+        Self: Sized,
+    {
+        FindOrdCombineLower { combined: (self, other), snap: SnapPolicy::None }
+    }
+
+    /// Marks all results in the direction(s) of `snap` as valid such that the search snaps accordingly if there's no match.
+    fn snap(self, snap: SnapPolicy) -> Snapped<Self>
+    where
+        Self: Sized,
+    {
+        Snapped { target: self, snap }
+    }
+
+    /// Marks all results below the target as valid such that the search snaps downwards if there's no match.
+    fn snap_down(self) -> Snapped<Self>
+    where
+        Self: Sized,
+    {
+        self.snap(SnapPolicy::Down)
+    }
+
+    /// Marks all results above the target as valid such that the search snaps upwards if there's no match.
+    fn snap_up(self) -> Snapped<Self>
+    where
+        Self: Sized,
+    {
+        self.snap(SnapPolicy::Up)
+    }
+
+    /// Limits snapping to values whose distance from the target (as computed by `distance`) is at most `tolerance`.
+    /// Results further away are invalid, so if there's no match and no valid result within the tolerance,
+    /// the search doesn't find anything.
+    fn within<K: PartialOrd, D: Fn(&T) -> K>(self, distance: D, tolerance: K) -> Within<Self, D, K>
+    where
+        Self: Sized,
+    {
+        Within { target: self, distance, tolerance }
+    }
+
+    /// Compares values of type `U` by the key extracted from them using `key`.
+    fn by_key<U, G: Fn(&U) -> T>(self, key: G) -> ByKey<Self, G>
+    where
+        Self: Sized,
+    {
+        ByKey { target: self, key }
+    }
+
+    /// Reverses the ordering of the target for searching values sorted in descending order.
+    fn reversed(self) -> Reversed<Self>
+    where
+        Self: Sized,
+    {
+        Reversed { target: self }
+    }
+
+    /// Handles values that are incomparable with the target (like NaN) according to `policy`
+    /// instead of skipping them.
+    fn on_incomparable(self, policy: IncomparablePolicy) -> OnIncomparable<Self>
+    where
+        Self: Sized,
+    {
+        OnIncomparable { target: self, policy }
+    }
+
+    /// Maps the errors returned by the target using `f`.
+    fn map_err<E2, G: Fn(E) -> E2>(self, f: G) -> MapErr<Self, G, E>
+    where
+        Self: Sized,
+    {
+        MapErr { target: self, f, err: PhantomData }
+    }
+}
+
+impl<T, E, F: FindOrd<T, E> + ?Sized> FindOrdExt<T, E> for F {}
+
+/// Target that forces results in the direction(s) of `snap` to be valid.
+/// See [`FindOrdExt::snap`].
+pub struct Snapped<F> {
+    pub target: F,
+    pub snap: SnapPolicy,
+}
+
+impl<T, E, F: FindOrd<T, E>> FindOrd<T, E> for Snapped<F> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(
+            match self.target.cmp(t)? {
+                FindOrdering::ValBelowTarget { is_valid_res } => FindOrdering::ValBelowTarget { is_valid_res: self.snap.snaps_downwards() || is_valid_res },
+                FindOrdering::ValAboveTarget { is_valid_res } => FindOrdering::ValAboveTarget { is_valid_res: self.snap.snaps_upwards() || is_valid_res },
+                r @ (FindOrdering::ValMatchesTarget | FindOrdering::ValIncomparable) => r,
+            }
+        )
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        match self.snap {
+            SnapPolicy::Down => Ok(true),
+            SnapPolicy::Up => Ok(false),
+            _ => self.target.prefer_snap_downwards(below, above),
+        }
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+/// Target that limits the valid results of the wrapped target to values within a tolerance.
+/// See [`FindOrdExt::within`].
+pub struct Within<F, D, K> {
+    pub target: F,
+    pub distance: D,
+    pub tolerance: K,
+}
+
+impl<F, D, K: PartialOrd> Within<F, D, K> {
+    fn is_within<T>(&self, t: &T) -> bool where D: Fn(&T) -> K {
+        matches!((self.distance)(t).partial_cmp(&self.tolerance), Some(Ordering::Less | Ordering::Equal))
+    }
+}
+
+impl<T, E, F: FindOrd<T, E>, D: Fn(&T) -> K, K: PartialOrd> FindOrd<T, E> for Within<F, D, K> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(
+            match self.target.cmp(t)? {
+                FindOrdering::ValBelowTarget { is_valid_res } => FindOrdering::ValBelowTarget { is_valid_res: is_valid_res && self.is_within(t) },
+                FindOrdering::ValAboveTarget { is_valid_res } => FindOrdering::ValAboveTarget { is_valid_res: is_valid_res && self.is_within(t) },
+                r @ (FindOrdering::ValMatchesTarget | FindOrdering::ValIncomparable) => r,
+            }
+        )
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        self.target.prefer_snap_downwards(below, above)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+/// Target that compares values by a key extracted from them.
+/// See [`FindOrdExt::by_key`].
+pub struct ByKey<F, G> {
+    pub target: F,
+    pub key: G,
+}
+
+impl<T, U, E, F: FindOrd<T, E>, G: Fn(&U) -> T> FindOrd<U, E> for ByKey<F, G> {
+    fn cmp(&self, t: &U) -> Result<FindOrdering, E> {
+        self.target.cmp(&(self.key)(t))
+    }
+
+    fn prefer_snap_downwards(&self, below: &U, above: &U) -> Result<bool, E> {
+        self.target.prefer_snap_downwards(&(self.key)(below), &(self.key)(above))
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+/// Target with the ordering of the wrapped target reversed.
+/// See [`FindOrdExt::reversed`].
+pub struct Reversed<F> {
+    pub target: F,
+}
+
+impl<T, E, F: FindOrd<T, E>> FindOrd<T, E> for Reversed<F> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(
+            match self.target.cmp(t)? {
+                FindOrdering::ValBelowTarget { is_valid_res } => FindOrdering::ValAboveTarget { is_valid_res },
+                FindOrdering::ValAboveTarget { is_valid_res } => FindOrdering::ValBelowTarget { is_valid_res },
+                r @ (FindOrdering::ValMatchesTarget | FindOrdering::ValIncomparable) => r,
+            }
+        )
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        // The candidates switch sides in the ordering of the wrapped target.
+        Ok(!self.target.prefer_snap_downwards(above, below)?)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+/// Target that handles values that are incomparable with the wrapped target according to `policy`.
+/// See [`FindOrdExt::on_incomparable`].
+pub struct OnIncomparable<F> {
+    pub target: F,
+    pub policy: IncomparablePolicy,
+}
+
+impl<T, E: From<IncomparableError>, F: FindOrd<T, E>> FindOrd<T, E> for OnIncomparable<F> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(
+            match self.target.cmp(t)? {
+                FindOrdering::ValIncomparable => match self.policy {
+                    IncomparablePolicy::Error => return Err(IncomparableError.into()),
+                    IncomparablePolicy::Below => FindOrdering::below(SnapPolicy::None),
+                    IncomparablePolicy::Above => FindOrdering::above(SnapPolicy::None),
+                    IncomparablePolicy::Skip => FindOrdering::ValIncomparable,
+                },
+                r => r,
+            }
+        )
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        self.target.prefer_snap_downwards(below, above)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+/// Target that maps the errors of the wrapped target.
+/// See [`FindOrdExt::map_err`].
+pub struct MapErr<F, G, E> {
+    pub target: F,
+    pub f: G,
+    err: PhantomData<fn(E)>,
+}
+
+impl<T, E, E2, F: FindOrd<T, E>, G: Fn(E) -> E2> FindOrd<T, E2> for MapErr<F, G, E> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E2> {
+        self.target.cmp(t).map_err(&self.f)
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E2> {
+        self.target.prefer_snap_downwards(below, above).map_err(&self.f)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        self.target.cost_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::find;
+    use crate::snap::with_snap;
+    use crate::test_util::helpers::*;
+
+    /// Plain target with fixed types such that they don't need to be specified in chained calls.
+    fn val(v: i64) -> impl FindOrd<i64, String> {
+        v
+    }
+
+    #[test]
+    fn and_of_lower_bounds_finds_first_value_satisfying_both() {
+        let arr = [0, 2, 4, 6, 8];
+        let t = val(3).snap_up().and(val(5).snap_up());
+        assert_eq!(find_idx(&arr, &t), Some(3));
+    }
+
+    #[test]
+    fn or_of_lower_bounds_finds_first_value_satisfying_either() {
+        let arr = [0, 2, 4, 6, 8];
+        let t = val(3).snap_up().or(val(5).snap_up());
+        assert_eq!(find_idx(&arr, &t), Some(2));
+    }
+
+    #[test]
+    fn and_of_upper_bounds_is_expressed_by_or() {
+        let arr = [0, 2, 4, 6, 8];
+        let t = val(3).snap_down().or(val(5).snap_down());
+        assert_eq!(find_idx(&arr, &t), Some(1));
+    }
+
+    #[test]
+    fn snapping_direction_is_set_by_snap_down_and_snap_up() {
+        let arr = [0, 2, 4, 6, 8];
+        assert_eq!(find_idx(&arr, &val(5).snap_down()), Some(2));
+        assert_eq!(find_idx(&arr, &val(5).snap_up()), Some(3));
+        assert_eq!(find_idx(&arr, &val(5)), None);
+    }
+
+    #[test]
+    fn within_limits_snapping_to_tolerance() {
+        let arr = [0, 2, 4, 6, 8];
+        let distance = |v: &i64| v.abs_diff(5);
+        assert_eq!(find_idx(&arr, &val(5).snap_down().within(distance, 1)), Some(2));
+        assert_eq!(find_idx(&arr, &val(5).snap_up().within(distance, 1)), Some(3));
+        let distance = |v: &i64| v.abs_diff(3);
+        assert_eq!(find_idx(&arr, &val(3).snap_down().within(distance, 0)), None);
+        assert_eq!(find_idx(&arr, &val(3).within(distance, 10)), None);
+        let arr = [0, 10, 20];
+        let distance = |v: &i64| v.abs_diff(13);
+        assert_eq!(find_idx(&arr, &val(13).snap_down().within(distance, 2)), None);
+        assert_eq!(find_idx(&arr, &val(13).snap_up().within(distance, 7)), Some(2));
+    }
+
+    #[test]
+    fn combinations_can_be_snapped() {
+        let arr = [0, 2, 4, 6, 8];
+        let t = val(3).and(val(5)).snap_up();
+        assert_eq!(find_idx(&arr, &t), Some(3));
+    }
+
+    #[test]
+    fn by_key_compares_projected_field() {
+        let arr = [(0, "a"), (2, "b"), (4, "c"), (6, "d")];
+        let t = val(3).snap_down().by_key(|&(k, _): &(i64, &str)| k);
+        assert_eq!(find_idx(&arr, &t), Some(1));
+    }
+
+    #[test]
+    fn reversed_finds_value_in_descending_order() {
+        let arr = [8, 6, 4, 2, 0];
+        assert_eq!(find_idx(&arr, &val(4).reversed()), Some(2));
+        // Snapping downwards in the original ordering snaps to a greater index.
+        assert_eq!(find_idx(&arr, &val(5).snap_down().reversed()), Some(2));
+        assert_eq!(find_idx(&arr, &val(5).snap_up().reversed()), Some(1));
+    }
+
+    #[test]
+    fn reversed_keeps_preferred_candidate() {
+        let arr = [8, 6, 4, 2, 0];
+        let t = val(3).snap_up().or(val(7).snap_down()).reversed();
+        assert_eq!(find_idx(&arr, &t), Some(2));
+    }
+
+    #[test]
+    fn incomparable_values_are_handled_by_policy() {
+        let arr = [0.0, 1.0, f64::NAN, 3.0, 4.0];
+        let lookup = new_lookup(&arr);
+        let find_val = |target: &dyn FindOrd<f64, IncomparableError>| {
+            find(&|idx| Ok(lookup(idx).unwrap()), target, 0, arr.len() as i64 - 1)
+                .map(|r| r.element.map(|e| e.idx))
+                .map_err(|e| e.to_string())
+        };
+        let target = |policy| OnIncomparable { target: with_snap(2.5, SnapPolicy::Up), policy };
+        assert_eq!(find_val(&target(IncomparablePolicy::Skip)), Ok(Some(3)));
+        assert_eq!(find_val(&target(IncomparablePolicy::Below)), Ok(Some(3)));
+        // Treating NaN as above makes the search continue below it.
+        assert_eq!(find_val(&target(IncomparablePolicy::Above)), Ok(None));
+        assert_eq!(find_val(&target(IncomparablePolicy::Error)), Err("value is incomparable with target".to_string()));
+    }
+
+    #[test]
+    fn map_err_maps_errors() {
+        struct Failing;
+        impl FindOrd<i64, String> for Failing {
+            fn cmp(&self, t: &i64) -> Result<FindOrdering, String> {
+                Err(format!("cannot compare {}", t))
+            }
+        }
+        let t = Failing.map_err(|e| e.len());
+        assert_eq!(t.cmp(&4), Err(16));
+    }
+}
@@ -1,6 +1,17 @@
+use crate::snap::SnapPolicy;
+use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Result of comparing a value against a target.
+///
+/// Whether a non-matching value is a valid result is a plain `is_valid_res` flag rather than a [`SnapPolicy`]:
+/// It describes a single comparison after the target has applied its policy,
+/// which combined targets (see [`crate::combine`]) may decide independently of any one policy.
+/// Use [`FindOrdering::below`] and [`FindOrdering::above`] to derive it from a policy.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FindOrdering {
     /// Indicates that the target is greater than the candidate value that it was compared against.
     ValBelowTarget {
@@ -14,22 +25,169 @@ pub enum FindOrdering {
     },
     /// Indicates that the target is neither above nor below the value.
     ValMatchesTarget,
+    /// Indicates that the value cannot be compared against the target (e.g. because it's NaN).
+    /// The search skips such values, so they never match. Use [`IncomparablePolicy`] to handle them differently.
+    ValIncomparable,
+}
+
+/// Determines how values that are incomparable with a target (like NaN) are handled.
+/// See [`crate::ext::FindOrdExt::on_incomparable`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IncomparablePolicy {
+    /// Fail the search with [`IncomparableError`].
+    Error,
+    /// Treat the value as below the target (without being a valid result).
+    Below,
+    /// Treat the value as above the target (without being a valid result).
+    Above,
+    /// Skip the value as if it wasn't there.
+    ///
+    /// Skipping is done by probing the following values one at a time until a comparable one is found,
+    /// so each run of incomparable values costs a lookup and comparison per value.
+    /// In the worst case (e.g. mostly incomparable values), the search therefore takes linear rather than logarithmic time.
+    #[default]
+    Skip,
+}
+
+/// Error of comparing a value that is incomparable with the target under [`IncomparablePolicy::Error`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IncomparableError;
+
+impl Display for IncomparableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "value is incomparable with target")
+    }
+}
+
+impl std::error::Error for IncomparableError {}
+
+impl FindOrdering {
+    /// Result for a value below the target, which is valid if the target snaps downwards.
+    pub fn below(snap: SnapPolicy) -> FindOrdering {
+        FindOrdering::ValBelowTarget { is_valid_res: snap.snaps_downwards() }
+    }
+
+    /// Result for a value above the target, which is valid if the target snaps upwards.
+    pub fn above(snap: SnapPolicy) -> FindOrdering {
+        FindOrdering::ValAboveTarget { is_valid_res: snap.snaps_upwards() }
+    }
+
+    /// Result for a value given the ordering of the target relative to it (i.e. `target.partial_cmp(value)`).
+    /// Results that don't match the target are valid according to `snap`.
+    /// Incomparable values (like NaN) yield [`FindOrdering::ValIncomparable`] and are therefore skipped.
+    pub fn from_target_ordering(ord: Option<Ordering>, snap: SnapPolicy) -> FindOrdering {
+        match ord {
+            Some(Ordering::Less) => FindOrdering::above(snap),
+            Some(Ordering::Greater) => FindOrdering::below(snap),
+            Some(Ordering::Equal) => FindOrdering::ValMatchesTarget,
+            None => FindOrdering::ValIncomparable,
+        }
+    }
 }
 
 pub trait FindOrd<T, E> {
     fn cmp(&self, t: &T) -> Result<FindOrdering, E>;
+
+    /// Chooses the result of a search that didn't find any match but did record valid results both below and above the target.
+    /// The values `below` and `above` are the greatest and least of these, respectively.
+    /// Returns `true` to choose `below` (i.e. snap downwards) and `false` to choose `above`.
+    ///
+    /// Targets that only ever mark results in a single direction as valid never have to make this choice.
+    fn prefer_snap_downwards(&self, _below: &T, _above: &T) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    /// Hint of the relative cost of calling [`FindOrd::cmp`] on the target.
+    /// It's used for comparing the cheapest targets first in combinations that are able to skip the remaining ones
+    /// once the result is settled.
+    fn cost_hint(&self) -> u64 {
+        1
+    }
 }
 
-/// Let all [`PartialOrd`] types (of self) trivially implement [`FindOrd`].
-impl<T: PartialOrd<T>, E> FindOrd<T, E> for T {
-    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
-        Ok(
-            match self.partial_cmp(t) {
-                Some(Ordering::Less) => FindOrdering::ValAboveTarget { is_valid_res: false },
-                Some(Ordering::Greater) => FindOrdering::ValBelowTarget { is_valid_res: false },
-                _ => FindOrdering::ValMatchesTarget, // "match" is defined as neither above nor below
+/// Implements [`FindOrd`] for [`PartialOrd`] types as targets for values of the same type.
+///
+/// This is done per type rather than as a blanket implementation for all [`PartialOrd`] types
+/// so that other crates are free to implement [`FindOrd`] for their own types.
+/// Other [`PartialOrd`] types (like tuples, arrays or user-defined types) aren't targets themselves;
+/// wrap them using [`crate::snap::with_snap`] (with [`SnapPolicy::None`] for the same behavior) instead.
+macro_rules! impl_find_ord_for_partial_ord {
+    ($($t:ty),*) => {
+        $(
+            impl<E> FindOrd<$t, E> for $t {
+                fn cmp(&self, t: &$t) -> Result<FindOrdering, E> {
+                    Ok(FindOrdering::from_target_ordering(self.partial_cmp(t), SnapPolicy::None))
+                }
             }
-        )
+        )*
+    };
+}
+
+impl_find_ord_for_partial_ord!(
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+    f32, f64, bool, char, (),
+    Duration, Instant, SystemTime,
+    IpAddr, Ipv4Addr, Ipv6Addr
+);
+
+/// Lets strings be targets for any values that borrow as strings (e.g. searching [`String`] values using a `&str`).
+impl<T: Borrow<str>, E> FindOrd<T, E> for str {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.partial_cmp(t.borrow()), SnapPolicy::None))
+    }
+}
+
+impl<T: Borrow<str>, E> FindOrd<T, E> for String {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        FindOrd::cmp(self.as_str(), t)
+    }
+}
+
+/// Lets slices be targets for any values that borrow as slices (e.g. searching [`Vec`] values using a slice).
+impl<U: PartialOrd, T: Borrow<[U]>, E> FindOrd<T, E> for [U] {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.partial_cmp(t.borrow()), SnapPolicy::None))
+    }
+}
+
+impl<U: PartialOrd, T: Borrow<[U]>, E> FindOrd<T, E> for Vec<U> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        FindOrd::cmp(self.as_slice(), t)
+    }
+}
+
+impl<U: PartialOrd, E> FindOrd<Option<U>, E> for Option<U> {
+    fn cmp(&self, t: &Option<U>) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.partial_cmp(t), SnapPolicy::None))
+    }
+}
+
+impl<T, E, F: FindOrd<T, E> + ?Sized> FindOrd<T, E> for &F {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        (**self).cmp(t)
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        (**self).prefer_snap_downwards(below, above)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        (**self).cost_hint()
+    }
+}
+
+impl<T, E, F: FindOrd<T, E> + ?Sized> FindOrd<T, E> for Box<F> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        (**self).cmp(t)
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        (**self).prefer_snap_downwards(below, above)
+    }
+
+    fn cost_hint(&self) -> u64 {
+        (**self).cost_hint()
     }
 }
 
@@ -54,35 +212,64 @@ pub struct FindResult<T> {
 
 // TODO: Generify the index type (should be 'usize' for arrays).
 // TODO: Use custom error type? That would allow us to include the index if 'cmp' failed.
+/// Searches the index range `[lower_idx; upper_idx]` for an element matching the target.
+///
+/// If no element matches, the result is the nearest valid result (as determined by the target's snapping) instead.
+/// When valid results were recorded on both sides of the target, [`FindOrd::prefer_snap_downwards`] chooses between them,
+/// which picks the one below unless the target overrides it.
 pub fn find<T, E>(
     lookup: &impl Fn(i64) -> Result<T, E>,
     target: &dyn FindOrd<T, E>,
     mut lower_idx: i64, // inclusive
     mut upper_idx: i64, // inclusive
 ) -> Result<FindResult<T>, E> {
+    let mut res_below = None; // last (i.e. greatest) valid result below the target
+    let mut res_above = None; // last (i.e. least) valid result above the target
     let mut res = None;
-    while lower_idx <= upper_idx {
-        let idx = (lower_idx + upper_idx) / 2;
-        let val = lookup(idx)?;
-        match target.cmp(&val)? {
-            FindOrdering::ValBelowTarget { is_valid_res } => {
-                if is_valid_res {
-                    res = Some(Element { val, idx });
+    'search: while lower_idx <= upper_idx {
+        let mid_idx = (lower_idx + upper_idx) / 2;
+        // Skip incomparable values by probing the following ones.
+        let mut idx = mid_idx;
+        loop {
+            let val = lookup(idx)?;
+            match target.cmp(&val)? {
+                FindOrdering::ValBelowTarget { is_valid_res } => {
+                    if is_valid_res {
+                        res_below = Some(Element { val, idx });
+                    }
+                    lower_idx = idx + 1;
                 }
-                lower_idx = idx + 1;
-            }
-            FindOrdering::ValAboveTarget { is_valid_res } => {
-                if is_valid_res {
+                FindOrdering::ValAboveTarget { is_valid_res } => {
+                    if is_valid_res {
+                        res_above = Some(Element { val, idx });
+                    }
+                    upper_idx = mid_idx - 1; // the skipped values are excluded as well
+                }
+                FindOrdering::ValMatchesTarget => {
                     res = Some(Element { val, idx });
+                    break 'search;
+                }
+                FindOrdering::ValIncomparable => {
+                    idx += 1;
+                    if idx > upper_idx {
+                        // All values from 'mid_idx' and up are incomparable.
+                        upper_idx = mid_idx - 1;
+                    } else {
+                        continue;
+                    }
                 }
-                upper_idx = idx - 1;
-            }
-            FindOrdering::ValMatchesTarget => {
-                res = Some(Element { val, idx });
-                break;
             }
+            break;
         }
     }
+    if res.is_none() {
+        res = match (res_below, res_above) {
+            (Some(b), Some(a)) => {
+                if target.prefer_snap_downwards(&b.val, &a.val)? { Some(b) } else { Some(a) }
+            }
+            (b, a) => b.or(a),
+        };
+    }
     Ok(FindResult {
         element: res,
         last_lower_idx: lower_idx,
@@ -93,19 +280,10 @@ pub fn find<T, E>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snap::{with_snap, SnapPolicy};
     use crate::test_util::helpers::*;
     use assert_matches::assert_matches; // use stdlib version once it's stable (https://github.com/rust-lang/rust/issues/82775)
 
-    fn find_value<T, E>(
-        lookup: impl Fn(i64) -> Result<T, E>,
-        target: &impl FindOrd<T, E>,
-        lower_idx: i64, // inclusive
-        upper_idx: i64, // inclusive
-    ) -> Result<Option<T>, E> {
-        let r = find(&lookup, target, lower_idx, upper_idx)?;
-        Ok(r.element.map(|v| v.val))
-    }
-
     /* MISC */
 
     #[test]
@@ -222,11 +400,11 @@ mod tests {
                 Ok(Some(r)) if r == v
             );
             assert_matches!(
-                find_value(new_lookup(&arr), &with_snap(v, Snap::Downwards), 0, 3),
+                find_value(new_lookup(&arr), &with_snap(v, SnapPolicy::Down), 0, 3),
                 Ok(Some(r)) if r == v
             );
             assert_matches!(
-                find_value(new_lookup(&arr), &with_snap(v, Snap::Upwards), 0, 3),
+                find_value(new_lookup(&arr), &with_snap(v, SnapPolicy::Up), 0, 3),
                 Ok(Some(r)) if r == v
             );
         }
@@ -235,7 +413,7 @@ mod tests {
     #[test]
     fn can_find_element_before_unmatched_target_using_backwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[0, 2]), &with_snap(1, Snap::Downwards), 0, 1),
+            find_value(new_lookup(&[0, 2]), &with_snap(1, SnapPolicy::Down), 0, 1),
             Ok(Some(0))
         );
     }
@@ -243,7 +421,7 @@ mod tests {
     #[test]
     fn can_find_element_after_unmatched_target_using_forwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[0, 2]), &with_snap(1, Snap::Upwards), 0, 1),
+            find_value(new_lookup(&[0, 2]), &with_snap(1, SnapPolicy::Up), 0, 1),
             Ok(Some(2))
         );
     }
@@ -251,7 +429,7 @@ mod tests {
     #[test]
     fn can_find_first_element_for_target_less_than_first_element_using_forwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[1, 2]), &with_snap(0, Snap::Upwards), 0, 1),
+            find_value(new_lookup(&[1, 2]), &with_snap(0, SnapPolicy::Up), 0, 1),
             Ok(Some(1))
         );
     }
@@ -259,7 +437,7 @@ mod tests {
     #[test]
     fn can_find_last_element_for_target_greater_than_last_element_using_backwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[0, 1]), &with_snap(2, Snap::Downwards), 0, 1),
+            find_value(new_lookup(&[0, 1]), &with_snap(2, SnapPolicy::Down), 0, 1),
             Ok(Some(1))
         );
     }
@@ -267,7 +445,7 @@ mod tests {
     #[test]
     fn cannot_find_element_before_target_less_than_first_element_using_backwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[1, 2]), &with_snap(0, Snap::Downwards), 0, 1),
+            find_value(new_lookup(&[1, 2]), &with_snap(0, SnapPolicy::Down), 0, 1),
             Ok(None)
         );
     }
@@ -275,8 +453,128 @@ mod tests {
     #[test]
     fn cannot_find_element_after_target_greater_than_last_element_using_forwards_snap() {
         assert_matches!(
-            find_value(new_lookup(&[0, 1]), &with_snap(2, Snap::Upwards), 0, 1),
+            find_value(new_lookup(&[0, 1]), &with_snap(2, SnapPolicy::Up), 0, 1),
             Ok(None)
         );
     }
+
+    /* INCOMPARABLE VALUES */
+
+    #[test]
+    fn nan_values_do_not_match() {
+        let arr = [0.0, f64::NAN, 2.0, 3.0];
+        assert_eq!(find_idx(&arr, &0.0), Some(0));
+        assert_eq!(find_idx(&arr, &1.0), None);
+        assert_eq!(find_idx(&arr, &2.0), Some(2));
+        assert_eq!(find_idx(&arr, &3.0), Some(3));
+        assert_eq!(find_idx(&arr, &f64::NAN), None);
+    }
+
+    #[test]
+    fn can_snap_across_nan_values() {
+        let arr = [0.0, f64::NAN, f64::NAN, 3.0, f64::NAN];
+        assert_eq!(find_idx(&arr, &with_snap(1.0, SnapPolicy::Up)), Some(3));
+        assert_eq!(find_idx(&arr, &with_snap(2.0, SnapPolicy::Down)), Some(0));
+        assert_eq!(find_idx(&arr, &with_snap(5.0, SnapPolicy::Down)), Some(3));
+        assert_eq!(find_idx(&arr, &with_snap(-1.0, SnapPolicy::Up)), Some(0));
+    }
+
+    #[test]
+    fn cannot_find_anything_among_only_nan_values() {
+        let arr = [f64::NAN, f64::NAN, f64::NAN];
+        assert_eq!(find_idx(&arr, &with_snap(1.0, SnapPolicy::Both)), None);
+    }
+
+    /* TARGET TYPES */
+
+    #[test]
+    fn can_find_strings_using_str() {
+        let arr = ["a".to_string(), "c".to_string(), "e".to_string()];
+        assert_eq!(find_value(new_lookup(&arr), &"c", 0, 2), Ok(Some("c".to_string())));
+        assert_eq!(find_value(new_lookup(&arr), &"d", 0, 2), Ok(None));
+        assert_eq!(find_value(new_lookup(&arr), &"e".to_string(), 0, 2), Ok(Some("e".to_string())));
+    }
+
+    #[test]
+    fn can_find_vecs_using_slice() {
+        let arr = [vec![1, 2], vec![1, 3], vec![2]];
+        let target: &[i32] = &[1, 3];
+        assert_eq!(find_value(new_lookup(&arr), &target, 0, 2), Ok(Some(vec![1, 3])));
+    }
+
+    #[test]
+    fn can_find_tuples_using_snapping_target() {
+        let arr = [(1, 'a'), (1, 'c'), (2, 'a')];
+        assert_eq!(find_value(new_lookup(&arr), &with_snap((1, 'c'), SnapPolicy::None), 0, 2), Ok(Some((1, 'c'))));
+        assert_eq!(find_value(new_lookup(&arr), &with_snap((1, 'd'), SnapPolicy::None), 0, 2), Ok(None));
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Record {
+        timestamp: u64,
+    }
+
+    impl PartialEq<Record> for u64 {
+        fn eq(&self, r: &Record) -> bool {
+            *self == r.timestamp
+        }
+    }
+
+    impl PartialOrd<Record> for u64 {
+        fn partial_cmp(&self, r: &Record) -> Option<Ordering> {
+            self.partial_cmp(&r.timestamp)
+        }
+    }
+
+    #[test]
+    fn can_find_record_using_value_of_other_type() {
+        let arr = [Record { timestamp: 10 }, Record { timestamp: 20 }, Record { timestamp: 30 }];
+        assert_eq!(find_value(new_lookup(&arr), &with_snap(20u64, SnapPolicy::None), 0, 2), Ok(Some(Record { timestamp: 20 })));
+        assert_eq!(find_value(new_lookup(&arr), &with_snap(25u64, SnapPolicy::Up), 0, 2), Ok(Some(Record { timestamp: 30 })));
+    }
+
+    /// Type that is [`PartialOrd`] but compares against targets in a custom way.
+    #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    struct Version(u32);
+
+    impl FindOrd<Version, String> for Version {
+        fn cmp(&self, t: &Version) -> Result<FindOrdering, String> {
+            // Only the major version (i.e. hundreds) is significant.
+            Ok(FindOrdering::from_target_ordering((self.0 / 100).partial_cmp(&(t.0 / 100)), SnapPolicy::None))
+        }
+    }
+
+    #[test]
+    fn partial_ord_type_can_implement_find_ord() {
+        let arr = [Version(100), Version(205), Version(310)];
+        assert_eq!(find_value(new_lookup(&arr), &Version(200), 0, 2), Ok(Some(Version(205))));
+    }
+
+    #[test]
+    fn can_find_using_boxed_target() {
+        let arr = [0, 2, 4];
+        let target: Box<dyn FindOrd<i64, String>> = Box::new(with_snap(3, SnapPolicy::Down));
+        assert_eq!(find_value(new_lookup(&arr), &target, 0, 2), Ok(Some(2)));
+    }
+
+    #[test]
+    fn prefers_result_below_when_both_sides_are_valid() {
+        let arr = [0, 2, 4, 6];
+        // The probes are 2 (below) and then 4 (above), so the last probed value would be above.
+        assert_eq!(find_value(new_lookup(&arr), &with_snap(3, SnapPolicy::Both), 0, 3), Ok(Some(2)));
+        assert_eq!(find_value(new_lookup(&arr), &with_snap(1, SnapPolicy::Both), 0, 3), Ok(Some(0)));
+
+        struct PreferAbove(i64);
+        impl FindOrd<i64, String> for PreferAbove {
+            fn cmp(&self, t: &i64) -> Result<FindOrdering, String> {
+                FindOrd::<i64, String>::cmp(&with_snap(self.0, SnapPolicy::Both), t)
+            }
+
+            fn prefer_snap_downwards(&self, _below: &i64, _above: &i64) -> Result<bool, String> {
+                Ok(false)
+            }
+        }
+        assert_eq!(find_value(new_lookup(&arr), &PreferAbove(3), 0, 3), Ok(Some(4)));
+        assert_eq!(find_value(new_lookup(&arr), &PreferAbove(1), 0, 3), Ok(Some(2)));
+    }
 }
@@ -0,0 +1,128 @@
+use crate::find::{find, Element, FindOrd, FindResult};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Finds the (up to) `k` elements nearest to the target within the index range `[lower_idx; upper_idx]`.
+///
+/// The target is first searched using [`find`]. If it's matched, the matching element is the nearest one.
+/// Otherwise the search starts at the position where the target would have been.
+/// From there, the range is expanded outwards one element at a time,
+/// choosing the side whose next element has the smaller distance as computed by `distance`.
+/// If the distances are equal (or incomparable), the element below is chosen.
+///
+/// The elements are returned in index order.
+pub fn find_k_nearest<T, E, K: PartialOrd>(
+    lookup: &impl Fn(i64) -> Result<T, E>,
+    target: &dyn FindOrd<T, E>,
+    distance: impl Fn(&T) -> K,
+    k: usize,
+    lower_idx: i64, // inclusive
+    upper_idx: i64, // inclusive
+) -> Result<Vec<Element<T>>, E> {
+    if k == 0 || lower_idx > upper_idx {
+        return Ok(Vec::new());
+    }
+    let mut res = VecDeque::with_capacity(k.min((upper_idx - lower_idx + 1) as usize));
+    let FindResult { element, last_lower_idx, last_upper_idx } = find(lookup, target, lower_idx, upper_idx)?;
+    // The search only stops before the limits cross if the target was matched;
+    // otherwise, any element is the result of snapping and isn't necessarily nearest.
+    let (mut below_idx, mut above_idx) = match element {
+        Some(e) if last_lower_idx <= last_upper_idx => {
+            let idx = e.idx;
+            res.push_back(e);
+            (idx - 1, idx + 1)
+        }
+        _ => (last_upper_idx, last_lower_idx),
+    };
+
+    // Next candidates on each side along with their distances.
+    let next = |idx: i64| -> Result<Option<(Element<T>, K)>, E> {
+        if idx < lower_idx || idx > upper_idx {
+            return Ok(None);
+        }
+        let val = lookup(idx)?;
+        let d = distance(&val);
+        Ok(Some((Element { val, idx }, d)))
+    };
+    let mut below = next(below_idx)?;
+    let mut above = next(above_idx)?;
+    while res.len() < k {
+        let take_below = match (&below, &above) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some((_, db)), Some((_, da))) => db.partial_cmp(da) != Some(Ordering::Greater),
+        };
+        if take_below {
+            let (e, _) = below.take().unwrap();
+            res.push_front(e);
+            below_idx -= 1;
+            below = next(below_idx)?;
+        } else {
+            let (e, _) = above.take().unwrap();
+            res.push_back(e);
+            above_idx += 1;
+            above = next(above_idx)?;
+        }
+    }
+    Ok(res.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snap::{with_snap, SnapPolicy};
+    use crate::test_util::helpers::*;
+
+    fn find_k_nearest_idxs(arr: &[i64], target: i64, k: usize, lower_idx: i64, upper_idx: i64) -> Vec<i64> {
+        find_k_nearest(&new_lookup(arr), &target, |v| v.abs_diff(target), k, lower_idx, upper_idx)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.idx)
+            .collect()
+    }
+
+    #[test]
+    fn can_find_k_nearest_around_matched_target() {
+        let arr = [0, 10, 20, 22, 30, 40];
+        assert_eq!(find_k_nearest_idxs(&arr, 20, 1, 0, 5), vec![2]);
+        assert_eq!(find_k_nearest_idxs(&arr, 20, 2, 0, 5), vec![2, 3]);
+        assert_eq!(find_k_nearest_idxs(&arr, 20, 3, 0, 5), vec![1, 2, 3]);
+        assert_eq!(find_k_nearest_idxs(&arr, 20, 4, 0, 5), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn can_find_k_nearest_around_unmatched_target() {
+        let arr = [0, 10, 20, 22, 30, 40];
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 1, 0, 5), vec![2]);
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 2, 0, 5), vec![2, 3]);
+        assert_eq!(find_k_nearest_idxs(&arr, 27, 2, 0, 5), vec![3, 4]);
+        assert_eq!(find_k_nearest_idxs(&arr, -5, 2, 0, 5), vec![0, 1]);
+        assert_eq!(find_k_nearest_idxs(&arr, 45, 2, 0, 5), vec![4, 5]);
+    }
+
+    #[test]
+    fn k_nearest_prefers_lower_element_on_equal_distance() {
+        let arr = [0, 10, 20];
+        assert_eq!(find_k_nearest_idxs(&arr, 5, 1, 0, 2), vec![0]);
+        assert_eq!(find_k_nearest_idxs(&arr, 15, 1, 0, 2), vec![1]);
+    }
+
+    #[test]
+    fn k_nearest_respects_search_bounds() {
+        let arr = [0, 10, 20, 22, 30, 40];
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 4, 2, 3), vec![2, 3]);
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 3, 3, 5), vec![3, 4, 5]);
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 10, 0, 5), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(find_k_nearest_idxs(&arr, 21, 0, 0, 5), vec![]);
+        assert_eq!(find_k_nearest_idxs(&[], 21, 2, 0, -1), vec![]);
+        assert_eq!(find_k_nearest_idxs(&arr, 21, usize::MAX, 0, 5), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn k_nearest_ignores_snapping_of_target() {
+        let arr = [0, 10, 20];
+        let r = find_k_nearest(&new_lookup(&arr), &with_snap(16, SnapPolicy::Down), |v: &i64| v.abs_diff(16), 1, 0, 2).unwrap();
+        assert_eq!(r.into_iter().map(|e| e.idx).collect::<Vec<_>>(), vec![2]);
+    }
+}
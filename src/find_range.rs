@@ -1,4 +1,5 @@
 use crate::find::{find, Element, FindOrd, FindOrdering, FindResult};
+use crate::snap::SnapPolicy;
 use std::cmp::{max, min};
 
 struct FindOrdRange<'a, T, E> {
@@ -9,24 +10,29 @@ struct FindOrdRange<'a, T, E> {
 impl<T, E> FindOrd<T, E> for FindOrdRange<'_, T, E> {
     fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
         Ok(
-            if let FindOrdering::ValAboveTarget { .. } = self.upper.cmp(t)? {
-                FindOrdering::ValAboveTarget { is_valid_res: false } // erasing 'is_valid_res'
-            } else if let FindOrdering::ValBelowTarget { .. } = self.lower.cmp(t)? {
-                FindOrdering::ValBelowTarget { is_valid_res: false } // erasing 'is_valid_res'
-            } else {
-                FindOrdering::ValMatchesTarget
+            match self.upper.cmp(t)? {
+                FindOrdering::ValAboveTarget { .. } => FindOrdering::above(SnapPolicy::None), // erasing 'is_valid_res'
+                FindOrdering::ValIncomparable => FindOrdering::ValIncomparable,
+                _ => match self.lower.cmp(t)? {
+                    FindOrdering::ValBelowTarget { .. } => FindOrdering::below(SnapPolicy::None), // erasing 'is_valid_res'
+                    FindOrdering::ValIncomparable => FindOrdering::ValIncomparable,
+                    _ => FindOrdering::ValMatchesTarget,
+                },
             }
         )
     }
 }
 
+/// Elements found for the lower and upper target, respectively, of a range.
+pub type FindRangeResult<T> = (Option<Element<T>>, Option<Element<T>>);
+
 pub fn find_range<T, E>(
     lookup: &impl Fn(i64) -> Result<T, E>,
     lower_target: &dyn FindOrd<T, E>,
     upper_target: &dyn FindOrd<T, E>,
     lower_idx: i64, // inclusive
     upper_idx: i64, // inclusive
-) -> Result<(Option<Element<T>>, Option<Element<T>>), E> {
+) -> Result<FindRangeResult<T>, E> {
     // println!("find in range {}-{}", lower_idx, upper_idx);
     let FindResult { element, last_lower_idx, last_upper_idx } = find(
         lookup,
@@ -73,14 +79,15 @@ pub fn find_range<T, E>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snap::{with_snap, SnapPolicy};
     use crate::test_util::helpers::*;
     use assert_matches::assert_matches; // use stdlib version once it's stable (https://github.com/rust-lang/rust/issues/82775)
 
     fn all_snap_variants<E>(v: i64) -> Vec<Box<dyn FindOrd<i64, E>>> {
         vec![
             Box::new(v),
-            Box::new(with_snap(v, Snap::Downwards)),
-            Box::new(with_snap(v, Snap::Upwards)),
+            Box::new(with_snap(v, SnapPolicy::Down)),
+            Box::new(with_snap(v, SnapPolicy::Up)),
         ]
     }
 
@@ -141,11 +148,11 @@ mod tests {
             Ok((None, Some(u))) if u.val == 4
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &4, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &4, 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 4
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Upwards), &4, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Up), &4, 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 2 && u.val == 4
         );
     }
@@ -158,11 +165,11 @@ mod tests {
             Ok((Some(l), None)) if l.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &0, &with_snap(3, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &0, &with_snap(3, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &0, &with_snap(3, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &0, &with_snap(3, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 4
         );
     }
@@ -171,19 +178,19 @@ mod tests {
     fn snap_lower_and_upper() {
         let arr = &[0, 2, 4];
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &with_snap(3, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &with_snap(3, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &with_snap(3, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &with_snap(3, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 4
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Upwards), &with_snap(3, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Up), &with_snap(3, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 2 && u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Upwards), &with_snap(3, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Up), &with_snap(3, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 2 && u.val == 4
         );
     }
@@ -194,7 +201,7 @@ mod tests {
         // If this bound is used directly, then the snap value won't be found.
         let arr = &[-1, 0, 2, 4];
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &4, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &4, 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 4
         );
     }
@@ -205,7 +212,7 @@ mod tests {
         // If this bound is used directly, then the snap value won't be found.
         let arr = &[0, 2, 4, 5];
         assert_matches!(
-            find_range(&new_lookup(arr), &0, &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &0, &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 2
         );
     }
@@ -218,27 +225,27 @@ mod tests {
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &1, 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Upwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Up), &1, 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &1, &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &1, &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &1, &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &1, &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Upwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Up), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 2
         );
     }
@@ -251,15 +258,15 @@ mod tests {
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(-1, Snap::Upwards), &-1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(-1, SnapPolicy::Up), &-1, 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &-1, &with_snap(-1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &-1, &with_snap(-1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(-1, Snap::Upwards), &with_snap(-1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(-1, SnapPolicy::Up), &with_snap(-1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 0
         );
     }
@@ -272,15 +279,15 @@ mod tests {
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &1, 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &1, &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &1, &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(1, Snap::Downwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(1, SnapPolicy::Down), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 0
         );
     }
@@ -300,35 +307,35 @@ mod tests {
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &3, &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &3, &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &3, &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &3, &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &1, 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 1
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 1
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 1 && u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &1, 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 2
         );
     }
@@ -346,35 +353,35 @@ mod tests {
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &3, &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &3, &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &3, &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &3, &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &1, 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((Some(l), None)) if l.val == 0
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Downwards), &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Down), &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((Some(l), Some(u))) if l.val == 0 && u.val == 2
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &1, 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &1, 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &with_snap(1, Snap::Downwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &with_snap(1, SnapPolicy::Down), 0, arr.len() as i64 - 1),
             Ok((None, None))
         );
         assert_matches!(
-            find_range(&new_lookup(arr), &with_snap(3, Snap::Upwards), &with_snap(1, Snap::Upwards), 0, arr.len() as i64 - 1),
+            find_range(&new_lookup(arr), &with_snap(3, SnapPolicy::Up), &with_snap(1, SnapPolicy::Up), 0, arr.len() as i64 - 1),
             Ok((None, Some(u))) if u.val == 2
         );
     }
@@ -0,0 +1,132 @@
+use crate::find::{FindOrd, FindOrdering};
+use crate::snap::SnapPolicy;
+
+/// Maximum difference between floats that are considered equal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// Absolute difference.
+    Absolute(f64),
+    /// Difference relative to the greater magnitude of the two values.
+    Relative(f64),
+    /// Number of representable values between the two values (units in the last place).
+    Ulps(u64),
+}
+
+impl Tolerance {
+    /// Returns whether `a` and `b` are equal within the tolerance.
+    /// NaN isn't equal to anything, while infinities are only equal to themselves.
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        if a == b {
+            return true;
+        }
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+        match *self {
+            Tolerance::Absolute(eps) => (a - b).abs() <= eps,
+            Tolerance::Relative(eps) => (a - b).abs() <= eps * a.abs().max(b.abs()),
+            Tolerance::Ulps(ulps) => ulps_between(a, b) <= ulps as u128,
+        }
+    }
+}
+
+/// Maps the float to an integer such that the ordering is preserved and adjacent floats map to adjacent integers
+/// (with both zeros mapping to 0).
+fn ordered_bits(x: f64) -> i128 {
+    let b = x.to_bits() as i64;
+    if b < 0 {
+        -((b & i64::MAX) as i128)
+    } else {
+        b as i128
+    }
+}
+
+fn ulps_between(a: f64, b: f64) -> u128 {
+    ordered_bits(a).abs_diff(ordered_bits(b))
+}
+
+/// Target that matches all floats that equal `value` within `tolerance`.
+///
+/// As the matching values form a contiguous range around `value`, values outside of it are below or above the target
+/// and snap according to `snap` just like for an exact target.
+/// Incomparable values (i.e. NaN) are skipped.
+#[derive(Clone, Debug)]
+pub struct ApproxTarget {
+    pub value: f64,
+    pub tolerance: Tolerance,
+    pub snap: SnapPolicy,
+}
+
+impl ApproxTarget {
+    /// Creates a target that doesn't snap.
+    pub fn new(value: f64, tolerance: Tolerance) -> Self {
+        ApproxTarget { value, tolerance, snap: SnapPolicy::None }
+    }
+}
+
+impl<E> FindOrd<f64, E> for ApproxTarget {
+    fn cmp(&self, t: &f64) -> Result<FindOrdering, E> {
+        Ok(
+            if self.tolerance.eq(self.value, *t) {
+                FindOrdering::ValMatchesTarget
+            } else {
+                FindOrdering::from_target_ordering(self.value.partial_cmp(t), self.snap)
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+
+    #[test]
+    fn absolute_tolerance_matches_nearby_values() {
+        let t = Tolerance::Absolute(0.01);
+        assert!(t.eq(1.0, 1.005));
+        assert!(t.eq(-1.0, -0.995));
+        assert!(!t.eq(1.0, 1.02));
+        assert!(!t.eq(f64::NAN, f64::NAN));
+        assert!(t.eq(f64::INFINITY, f64::INFINITY));
+        assert!(!t.eq(f64::INFINITY, f64::MAX));
+    }
+
+    #[test]
+    fn relative_tolerance_scales_with_magnitude() {
+        let t = Tolerance::Relative(1e-6);
+        assert!(t.eq(1e9, 1e9 + 100.0));
+        assert!(!t.eq(1e9, 1e9 + 10000.0));
+        assert!(!t.eq(1e-9, 2e-9));
+        assert!(t.eq(0.0, -0.0));
+    }
+
+    #[test]
+    fn ulps_tolerance_counts_representable_values() {
+        let t = Tolerance::Ulps(2);
+        assert!(t.eq(0.1 + 0.2, 0.3));
+        assert!(t.eq(1.0, f64::from_bits(1.0f64.to_bits() + 2)));
+        assert!(!t.eq(1.0, f64::from_bits(1.0f64.to_bits() + 3)));
+        // The smallest values of opposite signs are adjacent to zero.
+        assert!(t.eq(f64::from_bits(1), -f64::from_bits(1)));
+        assert!(!t.eq(f64::from_bits(2), -f64::from_bits(1)));
+    }
+
+    #[test]
+    fn can_find_computed_value() {
+        let arr = [0.1, 0.2, 0.1 + 0.2, 0.4];
+        assert_eq!(find_idx(&arr, &0.3), None);
+        assert_eq!(find_idx(&arr, &ApproxTarget::new(0.3, Tolerance::Ulps(1))), Some(2));
+        assert_eq!(find_idx(&arr, &ApproxTarget::new(0.3, Tolerance::Absolute(1e-9))), Some(2));
+    }
+
+    #[test]
+    fn snaps_outside_of_tolerance() {
+        let arr = [1.0, 2.0, 3.0];
+        let target = |value, snap| ApproxTarget { value, tolerance: Tolerance::Absolute(0.1), snap };
+        assert_eq!(find_idx(&arr, &target(2.05, SnapPolicy::None)), Some(1));
+        assert_eq!(find_idx(&arr, &target(2.5, SnapPolicy::None)), None);
+        assert_eq!(find_idx(&arr, &target(2.5, SnapPolicy::Down)), Some(1));
+        assert_eq!(find_idx(&arr, &target(2.5, SnapPolicy::Up)), Some(2));
+    }
+}
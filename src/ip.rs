@@ -0,0 +1,234 @@
+use crate::find::{find, Element, FindOrd, FindOrdering};
+use crate::find_range::{find_range, FindRangeResult};
+use crate::key::KeyTarget;
+use crate::op::Op;
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Block of IP addresses sharing a prefix, written in CIDR notation (e.g. `10.0.0.0/8` or `2001:db8::/32`).
+///
+/// Addresses are ordered as by [`IpAddr`], i.e. all IPv4 addresses are below all IPv6 addresses.
+/// IPv4-mapped IPv6 addresses (like `::ffff:10.0.0.1`) are therefore distinct from the corresponding IPv4 addresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cidr {
+    /// First address of the block (i.e. with all host bits cleared).
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    /// Creates the block of addresses that share the first `prefix_len` bits with `addr`.
+    /// Returns `None` if the prefix is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Cidr> {
+        let addr = match addr {
+            IpAddr::V4(a) if prefix_len <= 32 => IpAddr::V4(Ipv4Addr::from(u32::from(a) & !host_mask_v4(prefix_len))),
+            IpAddr::V6(a) if prefix_len <= 128 => IpAddr::V6(Ipv6Addr::from(u128::from(a) & !host_mask_v6(prefix_len))),
+            _ => return None,
+        };
+        Some(Cidr { addr, prefix_len })
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// First (i.e. least) address of the block.
+    pub fn first(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Last (i.e. greatest) address of the block.
+    pub fn last(&self) -> IpAddr {
+        match self.addr {
+            IpAddr::V4(a) => IpAddr::V4(Ipv4Addr::from(u32::from(a) | host_mask_v4(self.prefix_len))),
+            IpAddr::V6(a) => IpAddr::V6(Ipv6Addr::from(u128::from(a) | host_mask_v6(self.prefix_len))),
+        }
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        self.first() <= *addr && *addr <= self.last()
+    }
+}
+
+fn host_mask_v4(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shr(prefix_len as u32).unwrap_or(0)
+}
+
+fn host_mask_v6(prefix_len: u8) -> u128 {
+    u128::MAX.checked_shr(prefix_len as u32).unwrap_or(0)
+}
+
+/// Error of parsing an invalid [`Cidr`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseCidrError(String);
+
+impl Display for ParseCidrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid CIDR block '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseCidrError {}
+
+impl FromStr for Cidr {
+    type Err = ParseCidrError;
+
+    /// Parses a block like `10.0.0.0/8` or `2001:db8::/32`.
+    /// Host bits that are set in the address are cleared (i.e. `10.1.2.3/8` is `10.0.0.0/8`),
+    /// and a plain address is a block containing only that address.
+    fn from_str(s: &str) -> Result<Cidr, ParseCidrError> {
+        let err = || ParseCidrError(s.to_string());
+        let (addr, prefix_len) = match s.split_once('/') {
+            None => (s, None),
+            Some((a, l)) => (a, Some(l)),
+        };
+        let addr = addr.parse::<IpAddr>().map_err(|_| err())?;
+        let prefix_len = match prefix_len {
+            None if addr.is_ipv4() => 32,
+            None => 128,
+            Some(l) if !l.is_empty() && l.bytes().all(|b| b.is_ascii_digit()) => l.parse().map_err(|_| err())?,
+            Some(_) => return Err(err()),
+        };
+        Cidr::new(addr, prefix_len).ok_or_else(err)
+    }
+}
+
+impl Display for Cidr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// Target that compares addresses against the range of addresses in a CIDR block.
+///
+/// Addresses in the block are equal to the target, and the result is interpreted according to `op`:
+/// [`Op::Eq`] matches any address in the block, while [`Op::Ge`] and [`Op::Le`] find the first and last of these.
+#[derive(Clone, Debug)]
+pub struct CidrTarget {
+    pub cidr: Cidr,
+    pub op: Op,
+}
+
+impl<E> FindOrd<IpAddr, E> for CidrTarget {
+    fn cmp(&self, t: &IpAddr) -> Result<FindOrdering, E> {
+        let ord =
+            if *t < self.cidr.first() {
+                Ordering::Greater
+            } else if *t > self.cidr.last() {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            };
+        Ok(self.op.find_ordering(Some(ord), SnapPolicy::None))
+    }
+}
+
+/// Finds the first and last address in the index range `[lower_idx; upper_idx]` that are within the CIDR block.
+pub fn find_cidr_range<E>(
+    lookup: &impl Fn(i64) -> Result<IpAddr, E>,
+    cidr: Cidr,
+    lower_idx: i64, // inclusive
+    upper_idx: i64, // inclusive
+) -> Result<FindRangeResult<IpAddr>, E> {
+    let lower = CidrTarget { cidr, op: Op::Ge };
+    let upper = CidrTarget { cidr, op: Op::Le };
+    find_range(lookup, &lower, &upper, lower_idx, upper_idx)
+}
+
+/// Finds the element whose range of addresses contains `addr` among elements sorted by the start of their
+/// (non-overlapping) ranges, e.g. the records of a table of geolocation or ASN data.
+///
+/// This is the element with the greatest start address that isn't above `addr` (found by snapping downwards),
+/// provided that its range extends to `addr`.
+pub fn find_containing_range<T, E>(
+    lookup: &impl Fn(i64) -> Result<T, E>,
+    addr: IpAddr,
+    start: impl Fn(&T) -> IpAddr,
+    end: impl Fn(&T) -> IpAddr, // inclusive
+    lower_idx: i64, // inclusive
+    upper_idx: i64, // inclusive
+) -> Result<Option<Element<T>>, E> {
+    let target = KeyTarget { value: addr, key: start, snap: SnapPolicy::Down };
+    let element = find(lookup, &target, lower_idx, upper_idx)?.element;
+    Ok(element.filter(|e| addr <= end(&e.val)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn can_parse_cidr_blocks() {
+        assert_eq!(cidr("10.0.0.0/8").to_string(), "10.0.0.0/8");
+        assert_eq!(cidr("10.1.2.3/8").to_string(), "10.0.0.0/8");
+        assert_eq!(cidr("10.1.2.3").to_string(), "10.1.2.3/32");
+        assert_eq!(cidr("2001:db8::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(cidr("::1").to_string(), "::1/128");
+        for s in ["10.0.0.0/33", "::/129", "10.0.0.0/", "10.0.0.0/+8", "10.0.0/8", "x/8"] {
+            assert_eq!(s.parse::<Cidr>(), Err(ParseCidrError(s.to_string())), "{}", s);
+        }
+    }
+
+    #[test]
+    fn cidr_block_spans_first_to_last_address() {
+        assert_eq!((cidr("192.168.1.0/24").first(), cidr("192.168.1.0/24").last()), (ip("192.168.1.0"), ip("192.168.1.255")));
+        assert_eq!((cidr("0.0.0.0/0").first(), cidr("0.0.0.0/0").last()), (ip("0.0.0.0"), ip("255.255.255.255")));
+        assert_eq!(cidr("2001:db8::/32").last(), ip("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"));
+        assert_eq!(cidr("::/0").last(), ip("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"));
+        assert!(cidr("10.0.0.0/8").contains(&ip("10.255.0.1")));
+        assert!(!cidr("10.0.0.0/8").contains(&ip("11.0.0.0")));
+        assert!(!cidr("::/0").contains(&ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn can_find_addresses_in_cidr_block() {
+        let arr = ["9.255.255.255", "10.0.0.0", "10.1.0.0", "10.255.255.255", "11.0.0.0", "::1", "2001:db8::1", "2001:db9::"].map(ip);
+        let find_idxs = |c| {
+            let (l, u) = find_cidr_range(&new_lookup(&arr), cidr(c), 0, arr.len() as i64 - 1).unwrap();
+            (l.map(|e| e.idx), u.map(|e| e.idx))
+        };
+        assert_eq!(find_idxs("10.0.0.0/8"), (Some(1), Some(3)));
+        assert_eq!(find_idxs("10.1.0.0/16"), (Some(2), Some(2)));
+        assert_eq!(find_idxs("12.0.0.0/8"), (None, None));
+        assert_eq!(find_idxs("0.0.0.0/0"), (Some(0), Some(4)));
+        assert_eq!(find_idxs("2001:db8::/32"), (Some(6), Some(6)));
+        assert_eq!(find_idxs("::/0"), (Some(5), Some(7)));
+    }
+
+    #[test]
+    fn can_find_range_containing_address() {
+        // (start, end, ASN)
+        let arr = [
+            (ip("1.0.0.0"), ip("1.0.0.255"), 1),
+            (ip("1.0.4.0"), ip("1.0.7.255"), 2),
+            (ip("8.8.8.0"), ip("8.8.8.255"), 3),
+            (ip("2001:db8::"), ip("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"), 4),
+        ];
+        let find_asn = |addr| {
+            find_containing_range(&new_lookup(&arr), ip(addr), |r| r.0, |r| r.1, 0, arr.len() as i64 - 1)
+                .unwrap()
+                .map(|e| e.val.2)
+        };
+        assert_eq!(find_asn("1.0.0.0"), Some(1));
+        assert_eq!(find_asn("1.0.0.255"), Some(1));
+        assert_eq!(find_asn("1.0.1.0"), None);
+        assert_eq!(find_asn("1.0.5.7"), Some(2));
+        assert_eq!(find_asn("8.8.8.8"), Some(3));
+        assert_eq!(find_asn("0.255.255.255"), None);
+        assert_eq!(find_asn("9.0.0.0"), None);
+        assert_eq!(find_asn("2001:db8::8"), Some(4));
+        assert_eq!(find_asn("2001:db9::"), None);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::find::{FindOrd, FindOrdering};
+use crate::snap::SnapPolicy;
+
+/// Target that compares values by a key extracted from them, e.g. a field of a struct.
+///
+/// This allows searching records by a single field without implementing [`FindOrd`] for a custom type:
+/// The target `value` is compared against the result of calling `key` on the candidate value
+/// the same way as by a [`SnappingTarget`](crate::snap::SnappingTarget).
+///
+/// For searching by a projected key using any other target, see [`crate::ext::FindOrdExt::by_key`].
+pub struct KeyTarget<K, G> {
+    pub value: K,
+    pub key: G,
+    pub snap: SnapPolicy,
+}
+
+impl<K, G> KeyTarget<K, G> {
+    /// Creates a target that doesn't snap.
+    pub fn new(value: K, key: G) -> Self {
+        KeyTarget { value, key, snap: SnapPolicy::None }
+    }
+}
+
+impl<T, K: PartialOrd, E, G: Fn(&T) -> K> FindOrd<T, E> for KeyTarget<K, G> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.value.partial_cmp(&(self.key)(t)), self.snap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        name: &'static str,
+    }
+
+    #[test]
+    fn can_find_record_by_field() {
+        let arr = [
+            Record { id: 1, name: "a" },
+            Record { id: 3, name: "b" },
+            Record { id: 5, name: "c" },
+        ];
+        assert_eq!(find_idx(&arr, &KeyTarget::new(3, |r: &Record| r.id)), Some(1));
+        assert_eq!(find_idx(&arr, &KeyTarget::new("c", |r: &Record| r.name)), Some(2));
+        assert_eq!(find_idx(&arr, &KeyTarget::new(4, |r: &Record| r.id)), None);
+    }
+
+    #[test]
+    fn can_find_record_by_field_using_snap() {
+        let arr = [
+            Record { id: 1, name: "a" },
+            Record { id: 3, name: "b" },
+            Record { id: 5, name: "c" },
+        ];
+        let down = KeyTarget { value: 4, key: |r: &Record| r.id, snap: SnapPolicy::Down };
+        let up = KeyTarget { value: 4, key: |r: &Record| r.id, snap: SnapPolicy::Up };
+        assert_eq!(find_idx(&arr, &down), Some(1));
+        assert_eq!(find_idx(&arr, &up), Some(2));
+    }
+}
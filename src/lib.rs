@@ -1,4 +1,14 @@
+pub mod collate;
 pub mod combine;
+pub mod ext;
 pub mod find;
+pub mod find_nearest;
 pub mod find_range;
+pub mod float;
+pub mod ip;
+pub mod key;
+pub mod op;
+pub mod partition;
+pub mod prefix;
+pub mod snap;
 mod test_util;
@@ -0,0 +1,81 @@
+use crate::find::FindOrdering;
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+
+/// Comparison operator that determines which values a target matches and in which direction it snaps.
+///
+/// The inequalities find the boundary of the range of values that satisfy them:
+/// [`Op::Ge`] and [`Op::Gt`] find the least value that is greater than or equal to (respectively greater than) the target,
+/// and [`Op::Le`] and [`Op::Lt`] find the greatest value that is less than or equal to (respectively less than) the target.
+/// As these values are found by snapping, the inequalities never report a value as matching the target.
+/// [`Op::Eq`] only matches values equal to the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Converts the ordering of the target relative to a value (as returned by [`PartialOrd::partial_cmp`])
+    /// into the result of comparing the value against a target using the operator.
+    /// The snapping of the inequalities is determined by their direction; `snap` only applies to [`Op::Eq`].
+    pub fn find_ordering(self, ord: Option<Ordering>, snap: SnapPolicy) -> FindOrdering {
+        match (self, ord) {
+            (Op::Eq, ord) => FindOrdering::from_target_ordering(ord, snap),
+            // Equal values are valid results for the non-strict inequalities,
+            // but the search continues in order to find the outermost one.
+            (Op::Le, Some(Ordering::Equal)) => FindOrdering::below(SnapPolicy::Down),
+            (Op::Ge, Some(Ordering::Equal)) => FindOrdering::above(SnapPolicy::Up),
+            (Op::Lt, Some(Ordering::Equal)) => FindOrdering::above(SnapPolicy::Down),
+            (Op::Gt, Some(Ordering::Equal)) => FindOrdering::below(SnapPolicy::Up),
+            (Op::Lt | Op::Le, ord) => FindOrdering::from_target_ordering(ord, SnapPolicy::Down),
+            (Op::Gt | Op::Ge, ord) => FindOrdering::from_target_ordering(ord, SnapPolicy::Up),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::FindOrd;
+    use crate::test_util::helpers::*;
+
+    struct OpTarget {
+        op: Op,
+        value: i64,
+    }
+
+    impl FindOrd<i64, String> for OpTarget {
+        fn cmp(&self, t: &i64) -> Result<FindOrdering, String> {
+            Ok(self.op.find_ordering(self.value.partial_cmp(t), SnapPolicy::None))
+        }
+    }
+
+    #[test]
+    fn inequalities_find_boundary_of_satisfying_values() {
+        let arr = [1, 2, 2, 2, 3];
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Ge, value: 2 }), Some(1));
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Gt, value: 2 }), Some(4));
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Le, value: 2 }), Some(3));
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Lt, value: 2 }), Some(0));
+    }
+
+    #[test]
+    fn inequalities_without_satisfying_values_find_nothing() {
+        let arr = [1, 2, 3];
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Gt, value: 3 }), None);
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Ge, value: 4 }), None);
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Lt, value: 1 }), None);
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Le, value: 0 }), None);
+    }
+
+    #[test]
+    fn equality_only_matches_equal_value() {
+        let arr = [1, 3, 5];
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Eq, value: 3 }), Some(1));
+        assert_eq!(find_idx(&arr, &OpTarget { op: Op::Eq, value: 4 }), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::find::{find, Element, FindOrd, FindOrdering, FindResult};
+use crate::snap::SnapPolicy;
+
+/// Target that finds where a monotone predicate becomes true, i.e. the first value for which it holds.
+///
+/// The predicate must be false for all values up to some point and true for all values after it
+/// (like "good" and "bad" commits in `git bisect`).
+/// Values for which it's false are below the target and values for which it's true are above it, and the latter are valid results.
+/// No value ever matches the target, so the search always runs until it has found the point.
+pub struct PredicateTarget<P> {
+    pub predicate: P,
+}
+
+impl<T, E, P: Fn(&T) -> Result<bool, E>> FindOrd<T, E> for PredicateTarget<P> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(
+            if (self.predicate)(t)? {
+                FindOrdering::above(SnapPolicy::Up)
+            } else {
+                FindOrdering::below(SnapPolicy::None)
+            }
+        )
+    }
+}
+
+/// Elements on each side of the point where a predicate becomes true.
+#[derive(Debug)]
+pub struct PartitionPoint<T> {
+    /// Last element for which the predicate is false (if any).
+    pub last_false: Option<Element<T>>,
+    /// First element for which the predicate is true (if any).
+    pub first_true: Option<Element<T>>,
+}
+
+/// Finds the point in the index range `[lower_idx; upper_idx]` where the monotone `predicate` becomes true
+/// (see [`PredicateTarget`]), like [`slice::partition_point`] with the predicate negated.
+///
+/// The predicate is only evaluated for the elements probed by the search.
+/// The last element for which it's false is looked up again (without evaluating the predicate) once the point is found.
+pub fn partition_point<T, E>(
+    lookup: &impl Fn(i64) -> Result<T, E>,
+    predicate: impl Fn(&T) -> Result<bool, E>,
+    lower_idx: i64, // inclusive
+    upper_idx: i64, // inclusive
+) -> Result<PartitionPoint<T>, E> {
+    let FindResult { element, last_upper_idx, .. } = find(lookup, &PredicateTarget { predicate }, lower_idx, upper_idx)?;
+    // As nothing matches the target, the search only ends once the limits have crossed,
+    // leaving the upper limit at the last element for which the predicate is false.
+    let last_false =
+        if last_upper_idx >= lower_idx {
+            Some(Element { val: lookup(last_upper_idx)?, idx: last_upper_idx })
+        } else {
+            None
+        };
+    Ok(PartitionPoint { last_false, first_true: element })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+    use std::cell::Cell;
+
+    fn partition_idxs(arr: &[i64], threshold: i64) -> (Option<i64>, Option<i64>) {
+        let p = partition_point(&new_lookup(arr), |v| Ok(*v >= threshold), 0, arr.len() as i64 - 1).unwrap();
+        (p.last_false.map(|e| e.idx), p.first_true.map(|e| e.idx))
+    }
+
+    #[test]
+    fn finds_elements_around_partition_point() {
+        let arr = [1, 2, 3, 3, 3, 4, 5];
+        assert_eq!(partition_idxs(&arr, 3), (Some(1), Some(2)));
+        assert_eq!(partition_idxs(&arr, 4), (Some(4), Some(5)));
+        assert_eq!(partition_idxs(&arr, 5), (Some(5), Some(6)));
+    }
+
+    #[test]
+    fn partition_point_at_edges() {
+        let arr = [1, 2, 3];
+        assert_eq!(partition_idxs(&arr, 0), (None, Some(0)));
+        assert_eq!(partition_idxs(&arr, 4), (Some(2), None));
+        assert_eq!(partition_idxs(&[], 1), (None, None));
+    }
+
+    #[test]
+    fn partition_point_respects_search_bounds() {
+        let arr = [1, 2, 3, 4, 5];
+        let p = partition_point(&new_lookup(&arr), |v| Ok(*v >= 3), 2, 4).unwrap();
+        assert_eq!((p.last_false.map(|e| e.idx), p.first_true.map(|e| e.idx)), (None, Some(2)));
+        let p = partition_point(&new_lookup(&arr), |v| Ok(*v >= 3), 0, 1).unwrap();
+        assert_eq!((p.last_false.map(|e| e.idx), p.first_true.map(|e| e.idx)), (Some(1), None));
+    }
+
+    #[test]
+    fn bisects_with_logarithmic_number_of_predicate_evaluations() {
+        // First "bad" commit of 1000.
+        let commits = (0..1000).collect::<Vec<i64>>();
+        let evaluations = Cell::new(0);
+        let is_bad = |c: &i64| {
+            evaluations.set(evaluations.get() + 1);
+            Ok(*c >= 637)
+        };
+        let p = partition_point(&new_lookup(&commits), is_bad, 0, 999).unwrap();
+        assert_eq!((p.last_false.map(|e| e.val), p.first_true.map(|e| e.val)), (Some(636), Some(637)));
+        assert!(evaluations.get() <= 10, "{} evaluations", evaluations.get());
+    }
+
+    #[test]
+    fn predicate_error_is_propagated() {
+        let arr = [1, 2, 3];
+        let r = partition_point(&new_lookup(&arr), |_| Err("broken build".to_string()), 0, 2);
+        assert_eq!(r.err(), Some("broken build".to_string()));
+    }
+}
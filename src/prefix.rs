@@ -0,0 +1,144 @@
+use crate::find::{FindOrd, FindOrdering};
+use crate::find_range::{find_range, FindRangeResult};
+use crate::op::Op;
+use crate::snap::SnapPolicy;
+use std::cmp::Ordering;
+
+/// Returns the least byte string that is greater than all byte strings starting with `prefix`,
+/// i.e. the exclusive upper bound of the range of these strings.
+/// Returns `None` if there is no such string (i.e. the prefix is empty or consists only of `0xFF` bytes),
+/// in which case the range is unbounded.
+///
+/// The bound is found by dropping trailing `0xFF` bytes and incrementing the last remaining byte.
+/// For the UTF-8 encoding of a string, this may result in bytes that aren't valid UTF-8 (e.g. for a prefix ending in U+007F),
+/// which is why the bound is a byte string: It's still correct when compared against the bytes of other strings,
+/// as byte order of UTF-8 coincides with the code point order of [`str`].
+pub fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut bound = prefix.to_vec();
+    while let Some(b) = bound.pop() {
+        if b < u8::MAX {
+            bound.push(b + 1);
+            return Some(bound);
+        }
+    }
+    None
+}
+
+/// Target that compares byte strings (e.g. [`String`] or `Vec<u8>` values) against the range of strings starting with a prefix.
+///
+/// Values in the range are equal to the target, and the result is interpreted according to `op`:
+/// [`Op::Eq`] matches any value with the prefix, while [`Op::Ge`] and [`Op::Le`] find the first and last of these.
+///
+/// Strings are compared by their bytes (i.e. by code points for UTF-8), so the prefix is case-sensitive
+/// and strings in different Unicode normalization forms don't share prefixes.
+#[derive(Clone, Debug)]
+pub struct PrefixTarget {
+    prefix: Vec<u8>,
+    upper: Option<Vec<u8>>,
+    op: Op,
+}
+
+impl PrefixTarget {
+    /// Creates a target for the range of values starting with `prefix`.
+    pub fn new(prefix: impl AsRef<[u8]>, op: Op) -> Self {
+        let prefix = prefix.as_ref().to_vec();
+        let upper = prefix_upper_bound(&prefix);
+        PrefixTarget { prefix, upper, op }
+    }
+}
+
+impl<T: AsRef<[u8]>, E> FindOrd<T, E> for PrefixTarget {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        let t = t.as_ref();
+        let ord =
+            if t < self.prefix.as_slice() {
+                Ordering::Greater
+            } else if self.upper.as_deref().is_some_and(|u| t >= u) {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            };
+        Ok(self.op.find_ordering(Some(ord), SnapPolicy::None))
+    }
+}
+
+/// Finds the first and last element in the index range `[lower_idx; upper_idx]` that start with `prefix`.
+/// The elements must be sorted by their bytes.
+pub fn find_prefix_range<T: AsRef<[u8]>, E>(
+    lookup: &impl Fn(i64) -> Result<T, E>,
+    prefix: impl AsRef<[u8]>,
+    lower_idx: i64, // inclusive
+    upper_idx: i64, // inclusive
+) -> Result<FindRangeResult<T>, E> {
+    let lower = PrefixTarget::new(&prefix, Op::Ge);
+    let upper = PrefixTarget::new(&prefix, Op::Le);
+    find_range(lookup, &lower, &upper, lower_idx, upper_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::find;
+    use crate::test_util::helpers::*;
+
+    #[test]
+    fn upper_bound_increments_last_byte() {
+        assert_eq!(prefix_upper_bound(b"abc"), Some(b"abd".to_vec()));
+        assert_eq!(prefix_upper_bound(&[0x00]), Some(vec![0x01]));
+    }
+
+    #[test]
+    fn upper_bound_drops_trailing_max_bytes() {
+        assert_eq!(prefix_upper_bound(&[b'a', 0xFF, 0xFF]), Some(b"b".to_vec()));
+        assert_eq!(prefix_upper_bound(&[0xFF, 0xFF]), None);
+        assert_eq!(prefix_upper_bound(&[]), None);
+    }
+
+    #[test]
+    fn upper_bound_of_unicode_prefix() {
+        assert_eq!(prefix_upper_bound("é".as_bytes()), Some("ê".as_bytes().to_vec()));
+        // Not valid UTF-8, but still the least byte string above all strings with the prefix.
+        assert_eq!(prefix_upper_bound("a\u{7F}".as_bytes()), Some(vec![b'a', 0x80]));
+        assert_eq!(prefix_upper_bound("\u{10FFFF}".as_bytes()), Some(vec![0xF4, 0x8F, 0xBF, 0xC0]));
+    }
+
+    fn find_prefix_idxs<T: AsRef<[u8]> + Clone>(arr: &[T], prefix: impl AsRef<[u8]>) -> (Option<i64>, Option<i64>) {
+        let (l, u) = find_prefix_range(&new_lookup(arr), prefix, 0, arr.len() as i64 - 1).unwrap();
+        (l.map(|e| e.idx), u.map(|e| e.idx))
+    }
+
+    #[test]
+    fn can_find_range_of_strings_with_prefix() {
+        let arr = ["a", "ab", "abc", "abd", "b", "é", "éa", "ê", "\u{10FFFF}"];
+        assert_eq!(find_prefix_idxs(&arr, "ab"), (Some(1), Some(3)));
+        assert_eq!(find_prefix_idxs(&arr, "abc"), (Some(2), Some(2)));
+        assert_eq!(find_prefix_idxs(&arr, "a"), (Some(0), Some(3)));
+        assert_eq!(find_prefix_idxs(&arr, "é"), (Some(5), Some(6)));
+        assert_eq!(find_prefix_idxs(&arr, "\u{10FFFF}"), (Some(8), Some(8)));
+        assert_eq!(find_prefix_idxs(&arr, ""), (Some(0), Some(8)));
+    }
+
+    #[test]
+    fn cannot_find_range_of_missing_prefix() {
+        let arr = ["a", "ab", "c"];
+        assert_eq!(find_prefix_idxs(&arr, "b"), (None, None));
+        assert_eq!(find_prefix_idxs(&arr, "abc"), (None, None));
+        assert_eq!(find_prefix_idxs(&arr, "0"), (None, None));
+        assert_eq!(find_prefix_idxs(&arr, "d"), (None, None));
+    }
+
+    #[test]
+    fn can_find_range_of_byte_strings_with_max_bytes() {
+        let arr = [vec![0x01], vec![0x01, 0xFF], vec![0x01, 0xFF, 0x00], vec![0x01, 0xFF, 0xFF], vec![0x02]];
+        assert_eq!(find_prefix_idxs(&arr, [0x01, 0xFF]), (Some(1), Some(3)));
+        assert_eq!(find_prefix_idxs(&arr, [0x01]), (Some(0), Some(3)));
+        assert_eq!(find_prefix_idxs(&arr, [0xFF]), (None, None));
+    }
+
+    #[test]
+    fn equality_matches_any_string_with_prefix() {
+        let arr = ["ab".to_string(), "ba".to_string(), "bb".to_string(), "c".to_string()];
+        let found = find(&new_lookup(&arr), &PrefixTarget::new("b", Op::Eq), 0, 3).unwrap().element.map(|e| e.val);
+        assert!(found.is_some_and(|v| v.starts_with('b')));
+    }
+}
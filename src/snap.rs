@@ -0,0 +1,156 @@
+use crate::find::{FindOrd, FindOrdering};
+use std::cmp::Ordering;
+
+/// Policy for which results that don't match a target are valid,
+/// i.e. the direction(s) in which a search snaps to a value if the target isn't matched exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SnapPolicy {
+    /// Only exact matches are valid.
+    #[default]
+    None,
+    /// Snap to the greatest value below the target.
+    Down,
+    /// Snap to the least value above the target.
+    Up,
+    /// Snap to the value on either side of the target.
+    /// If both are found, the target chooses using [`FindOrd::prefer_snap_downwards`] (i.e. the one below by default).
+    Both,
+}
+
+impl SnapPolicy {
+    /// Returns whether results below the target are valid.
+    pub fn snaps_downwards(self) -> bool {
+        matches!(self, SnapPolicy::Down | SnapPolicy::Both)
+    }
+
+    /// Returns whether results above the target are valid.
+    pub fn snaps_upwards(self) -> bool {
+        matches!(self, SnapPolicy::Up | SnapPolicy::Both)
+    }
+}
+
+/// Target that matches a single value, snapping to a neighboring value according to `snap` if it isn't found.
+/// The value may be of a different type than the searched values as long as it can be compared against them.
+/// The ordering is determined using [`FindOrdering::from_target_ordering`], just like for plain [`PartialOrd`] targets.
+#[derive(Clone, Debug)]
+pub struct SnappingTarget<T> {
+    pub value: T,
+    pub snap: SnapPolicy,
+}
+
+impl<T, V: PartialOrd<T>, E> FindOrd<T, E> for SnappingTarget<V> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.value.partial_cmp(t), self.snap))
+    }
+}
+
+/// Creates a target for `value` that snaps according to `snap`.
+pub fn with_snap<T>(value: T, snap: SnapPolicy) -> SnappingTarget<T> {
+    SnappingTarget { value, snap }
+}
+
+/// Rule for choosing between two values that are equally near a target.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TieBreak {
+    /// Choose the value below the target.
+    #[default]
+    Down,
+    /// Choose the value above the target.
+    Up,
+}
+
+/// Target that matches a single value, snapping to the nearest neighboring value if it isn't found.
+///
+/// The distance between the target value and a candidate is computed as `distance(&value, candidate)`.
+/// If the neighbors on both sides are equally near (or their distances are incomparable), `tie_break` decides.
+pub struct NearestTarget<T, D> {
+    pub value: T,
+    pub distance: D,
+    pub tie_break: TieBreak,
+}
+
+impl<T, D> NearestTarget<T, D> {
+    /// Creates a target that breaks ties by choosing the value below it.
+    pub fn new(value: T, distance: D) -> Self {
+        NearestTarget { value, distance, tie_break: TieBreak::Down }
+    }
+}
+
+impl<T: PartialOrd, E, D: Fn(&T, &T) -> K, K: PartialOrd> FindOrd<T, E> for NearestTarget<T, D> {
+    fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
+        Ok(FindOrdering::from_target_ordering(self.value.partial_cmp(t), SnapPolicy::Both))
+    }
+
+    fn prefer_snap_downwards(&self, below: &T, above: &T) -> Result<bool, E> {
+        Ok(
+            match (self.distance)(&self.value, below).partial_cmp(&(self.distance)(&self.value, above)) {
+                Some(Ordering::Less) => true,
+                Some(Ordering::Greater) => false,
+                _ => self.tie_break == TieBreak::Down,
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::helpers::*;
+
+    #[test]
+    fn can_find_neighbor_of_unmatched_value_in_snap_direction() {
+        let arr = [0, 2, 4, 6];
+        assert_eq!(find_idx(&arr, &SnappingTarget { value: 3, snap: SnapPolicy::None }), None);
+        assert_eq!(find_idx(&arr, &with_snap(3, SnapPolicy::Down)), Some(1));
+        assert_eq!(find_idx(&arr, &with_snap(3, SnapPolicy::Up)), Some(2));
+        assert_eq!(find_idx(&arr, &with_snap(3, SnapPolicy::Both)), Some(1));
+        assert_eq!(find_idx(&arr, &with_snap(4, SnapPolicy::Both)), Some(2));
+    }
+
+    #[test]
+    fn snapping_both_ways_snaps_to_only_neighbor_at_edges() {
+        let arr = [0, 2, 4, 6];
+        assert_eq!(find_idx(&arr, &with_snap(-1, SnapPolicy::Both)), Some(0));
+        assert_eq!(find_idx(&arr, &with_snap(7, SnapPolicy::Both)), Some(3));
+    }
+
+    #[test]
+    fn nearest_target_snaps_to_nearer_neighbor() {
+        let arr = [0, 10, 20];
+        let distance = |a: &i64, b: &i64| a.abs_diff(*b);
+        let find_nearest = |value| find_idx(&arr, &NearestTarget::new(value, distance));
+        assert_eq!(find_nearest(3), Some(0));
+        assert_eq!(find_nearest(7), Some(1));
+        assert_eq!(find_nearest(10), Some(1));
+        assert_eq!(find_nearest(14), Some(1));
+        assert_eq!(find_nearest(16), Some(2));
+        assert_eq!(find_nearest(-5), Some(0));
+        assert_eq!(find_nearest(25), Some(2));
+    }
+
+    #[test]
+    fn nearest_target_snaps_upwards_if_value_above_is_nearer() {
+        let arr = [0.0, 2.0, 4.0];
+        let distance = |a: &f64, b: &f64| (a - b).abs();
+        assert_eq!(find_idx(&arr, &NearestTarget::new(3.9, distance)), Some(2));
+        assert_eq!(find_idx(&arr, &with_snap(3.9, SnapPolicy::Both)), Some(1));
+    }
+
+    #[test]
+    fn nearest_target_breaks_ties_by_rule() {
+        let arr = [0.0, 1.0, 2.0];
+        let distance = |a: &f64, b: &f64| (a - b).abs();
+        let mut target = NearestTarget::new(1.5, distance);
+        assert_eq!(find_idx(&arr, &target), Some(1));
+        target.tie_break = TieBreak::Up;
+        assert_eq!(find_idx(&arr, &target), Some(2));
+    }
+
+    #[test]
+    fn can_snap_values_of_any_partially_ordered_type() {
+        let arr = ["apple", "banana", "cherry"];
+        assert_eq!(find_idx(&arr, &with_snap("blueberry", SnapPolicy::Up)), Some(2));
+        let arr = [0.5, 1.5, 2.5];
+        assert_eq!(find_idx(&arr, &with_snap(1.0, SnapPolicy::Down)), Some(0));
+    }
+}
@@ -1,11 +1,6 @@
 #[cfg(test)]
 pub mod helpers {
-    use crate::find::{FindOrd, FindOrdering};
-
-    pub enum Snap {
-        Downwards,
-        Upwards,
-    }
+    use crate::find::{find, FindOrd};
 
     pub fn new_lookup<T: Clone>(arr: &[T]) -> impl Fn(i64) -> Result<T, String> + '_ {
         |idx| {
@@ -20,26 +15,18 @@ pub mod helpers {
         }
     }
 
-    pub struct SnappingTarget<T> {
-        value: T,
-        snap: Option<Snap>,
-    }
-
-    impl<T: PartialOrd, E> FindOrd<T, E> for SnappingTarget<T> {
-        fn cmp(&self, t: &T) -> Result<FindOrdering, E> {
-            Ok(
-                if &self.value < t {
-                    FindOrdering::ValAboveTarget { is_valid_res: matches!(self.snap, Some(Snap::Upwards)) }
-                } else if &self.value > t {
-                    FindOrdering::ValBelowTarget { is_valid_res: matches!(self.snap, Some(Snap::Downwards)) }
-                } else {
-                    FindOrdering::ValMatchesTarget
-                }
-            )
-        }
+    pub fn find_value<T, E>(
+        lookup: impl Fn(i64) -> Result<T, E>,
+        target: &impl FindOrd<T, E>,
+        lower_idx: i64, // inclusive
+        upper_idx: i64, // inclusive
+    ) -> Result<Option<T>, E> {
+        let r = find(&lookup, target, lower_idx, upper_idx)?;
+        Ok(r.element.map(|v| v.val))
     }
 
-    pub fn with_snap<T>(value: T, snap: Snap) -> SnappingTarget<T> {
-        SnappingTarget { value, snap: Some(snap) }
+    /// Index of the element found for `target` in all of `arr`.
+    pub fn find_idx<T: Clone>(arr: &[T], target: &dyn FindOrd<T, String>) -> Option<i64> {
+        find(&new_lookup(arr), target, 0, arr.len() as i64 - 1).unwrap().element.map(|e| e.idx)
     }
 }